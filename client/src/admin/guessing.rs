@@ -1,9 +1,12 @@
-use types::message::AdminInteraction;
+use types::{game::Solved, message::AdminInteraction};
 use yew::prelude::*;
 
+use crate::i18n::Text;
+
 #[derive(Debug, Clone, Properties, PartialEq)]
 pub struct Properties {
     pub callback: Callback<AdminInteraction>,
+    pub solved: Solved,
 }
 
 #[function_component]
@@ -15,16 +18,39 @@ pub fn Guessing(props: &Properties) -> Html {
             callback.emit(AdminInteraction::AcceptGuess);
         }
     };
+    let title = {
+        let callback = callback.clone();
+        move |_| {
+            callback.emit(AdminInteraction::AcceptTitle);
+        }
+    };
+    let artist = {
+        let callback = callback.clone();
+        move |_| {
+            callback.emit(AdminInteraction::AcceptArtist);
+        }
+    };
     let reject = {
         let callback = callback.clone();
         move |_| {
             callback.emit(AdminInteraction::RejectGuess);
         }
     };
+    let partial = if props.solved.title || props.solved.artist {
+        html! {}
+    } else {
+        html! {
+            <>
+                <button onclick={title}>{Text::OnlyTitle.get()}</button>
+                <button onclick={artist}>{Text::OnlyArtist.get()}</button>
+            </>
+        }
+    };
     html! {
         <>
-            <button onclick={accept}>{"Akzeptieren"}</button>
-            <button onclick={reject}>{"Ablehnen"}</button>
+            <button onclick={accept}>{Text::Accept.get()}</button>
+            { partial }
+            <button onclick={reject}>{Text::Reject.get()}</button>
         </>
     }
 }
@@ -1,6 +1,8 @@
 use types::{message::AdminInteraction, track::Track};
 use yew::prelude::*;
 
+use crate::i18n::Text;
+
 #[derive(Debug, Clone, Properties, PartialEq)]
 pub struct Properties {
     pub callback: Callback<AdminInteraction>,
@@ -23,7 +25,7 @@ pub fn Listening(props: &Properties) -> Html {
                 <div class="song-name-smaller">{song.name.clone()}</div>
                 <div class="song-artists">{song.artists.join(", ")}</div>
             </div>
-            <button class="admin-stop-song" {onclick}>{"Song stoppen"}</button>
+            <button class="admin-stop-song" {onclick}>{Text::StopSong.get()}</button>
         </div>
     }
 }
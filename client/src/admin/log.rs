@@ -0,0 +1,76 @@
+use types::game::{PlayedSong, Round};
+use yew::prelude::*;
+
+use crate::i18n::{after_seconds, verdict, Text};
+
+#[derive(Debug, Clone, Properties, PartialEq)]
+pub struct Properties {
+    pub songs: Vec<PlayedSong>,
+    pub rounds: Vec<Round>,
+}
+
+/// The time of day of the given Unix timestamp.
+fn time_of_day(seconds: u64) -> String {
+    let date = js_sys::Date::new(&((seconds * 1000) as f64).into());
+    format!("{:02}:{:02}", date.get_hours(), date.get_minutes())
+}
+
+/// Every song played so far and what happened during it, latest first.
+#[function_component]
+pub fn Log(props: &Properties) -> Html {
+    let songs: Html = props
+        .songs
+        .iter()
+        .rev()
+        .map(|song| {
+            let round = song
+                .round
+                .and_then(|round| props.rounds.get(round))
+                .map(|round| format!(" · {}", round.name))
+                .unwrap_or_default();
+            let buzzes: Html = song
+                .buzzes
+                .iter()
+                .map(|names| html! { <li class="admin-log-buzzes">{Text::Buzzes.get()}{": "}{names.join(", ")}</li> })
+                .collect();
+            let guesses: Html = song
+                .guesses
+                .iter()
+                .map(|guess| {
+                    html! {
+                        <li class="admin-log-guess">
+                            {format!(
+                                "{}: {} ({:+}) {}",
+                                guess.team,
+                                verdict(guess.title, guess.artist),
+                                guess.points,
+                                after_seconds(guess.after as f64 / 1000.0)
+                            )}
+                        </li>
+                    }
+                })
+                .collect();
+            html! {
+                <li class="admin-log-song">
+                    <div class="admin-log-track">
+                        {format!("{} – {}", song.track.artists.join(", "), song.track.name)}
+                    </div>
+                    <div class="admin-log-meta">{time_of_day(song.started)}{round}</div>
+                    <ul class="admin-log-events">
+                        { buzzes }
+                        { guesses }
+                    </ul>
+                </li>
+            }
+        })
+        .collect();
+    if props.songs.is_empty() {
+        return html! {};
+    }
+    html! {
+        <div class={classes!("admin-log", "container")}>
+            <div class="admin-log-text">{Text::History.get()}</div>
+            <ul class="admin-log-songs">{ songs }</ul>
+        </div>
+    }
+}
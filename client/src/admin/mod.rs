@@ -1,5 +1,9 @@
 mod guessing;
 mod listening;
+mod log;
+mod queue;
+mod rounds;
+mod scoring;
 mod selection;
 mod teams;
 
@@ -9,21 +13,38 @@ use either::Either::{self, Left, Right};
 use futures::{lock::Mutex, stream::SplitSink, SinkExt, StreamExt};
 use gloo::{
     console::error,
-    net::websocket::{futures::WebSocket, Message, WebSocketError},
+    net::{
+        http::Request,
+        websocket::{futures::WebSocket, Message, WebSocketError},
+    },
+    utils::window,
 };
 use guessing::Guessing;
 use listening::Listening;
+use log::Log;
+use queue::Queue;
+use rounds::Rounds;
+use scoring::Scoring;
 use selection::Selection;
 use teams::Teams;
 use types::{
-    game::{Phase, Team},
-    message::{AdminInteraction, AdminUpdate},
-    track::Track,
+    game::{Buzz, Phase, PlayedSong, Round, Team},
+    message::{AdminInteraction, AdminLog, AdminUpdate, NamePack},
+    scoring::ScoringRules,
+    track::{LibraryEntry, Track},
 };
 
-use wasm_bindgen::UnwrapThrowExt;
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use web_sys::HtmlInputElement;
 use yew::{platform::spawn_local, prelude::*};
 
+use crate::{
+    buzzes::Buzzes,
+    countdown::Countdown,
+    i18n::{buzzed, round_over, Text},
+    RoomProperties,
+};
+
 /// The internal state of the admin client
 #[derive(Debug, Clone)]
 pub enum Admin {
@@ -33,7 +54,22 @@ pub enum Admin {
     Initialized {
         phase: Phase,
         teams: Vec<Team>,
+        queue_buzzes: bool,
         song: Option<Track>,
+        library: Vec<LibraryEntry>,
+        queue: Vec<LibraryEntry>,
+        rules: ScoringRules,
+        buzzes: Vec<Buzz>,
+        answer_time: Option<u64>,
+        listening_time: Option<u64>,
+        rounds: Vec<Round>,
+        round: usize,
+        played: usize,
+        name_packs: Vec<NamePack>,
+        name_pack: Option<String>,
+        approve_names: bool,
+        time_left: Option<u64>,
+        log: Vec<PlayedSong>,
         sink: Arc<Mutex<SplitSink<WebSocket, Message>>>,
     },
 }
@@ -41,12 +77,14 @@ pub enum Admin {
 impl Admin {
     fn message_to_update(
         message: Result<Message, WebSocketError>,
-    ) -> Either<AdminInteraction, Result<AdminUpdate, bool>> {
+    ) -> Either<AdminInteraction, Result<Either<AdminUpdate, AdminLog>, bool>> {
         match message {
             Err(_) => Right(Err(false)),
             Ok(Message::Text(message)) => {
                 if let Ok(update) = serde_json::from_str::<AdminUpdate>(&message) {
-                    Right(Ok(update))
+                    Right(Ok(Left(update)))
+                } else if let Ok(log) = serde_json::from_str::<AdminLog>(&message) {
+                    Right(Ok(Right(log)))
                 } else {
                     Right(Err(true))
                 }
@@ -76,11 +114,11 @@ impl Admin {
 }
 
 impl Component for Admin {
-    type Message = Either<AdminInteraction, Result<AdminUpdate, bool>>;
-    type Properties = ();
+    type Message = Either<AdminInteraction, Result<Either<AdminUpdate, AdminLog>, bool>>;
+    type Properties = RoomProperties;
 
     fn create(ctx: &Context<Self>) -> Self {
-        let socket = WebSocket::open("ws/admin").unwrap_throw();
+        let socket = WebSocket::open(&format!("/ws/{}/admin", ctx.props().room)).unwrap_throw();
         let (sink, stream) = socket.split();
         ctx.link().send_stream(stream.map(Self::message_to_update));
         Self::Uninitialized {
@@ -94,11 +132,30 @@ impl Component for Admin {
                 self.send_interaction(interaction);
                 false
             }
-            Right(Ok(update)) => {
+            Right(Ok(Left(update))) => {
+                let log = match self {
+                    Self::Initialized { log, .. } => std::mem::take(log),
+                    Self::Uninitialized { .. } => Vec::new(),
+                };
                 *self = Self::Initialized {
                     phase: update.phase,
                     teams: update.teams,
+                    queue_buzzes: update.queue_buzzes,
                     song: update.song,
+                    library: update.library,
+                    queue: update.queue,
+                    rules: update.rules,
+                    buzzes: update.buzzes,
+                    answer_time: update.answer_time,
+                    listening_time: update.listening_time,
+                    rounds: update.rounds,
+                    round: update.round,
+                    played: update.played,
+                    name_packs: update.name_packs,
+                    name_pack: update.name_pack,
+                    approve_names: update.approve_names,
+                    time_left: update.time_left,
+                    log,
                     sink: Arc::clone(match self {
                         Self::Initialized { sink, .. } => sink,
                         Self::Uninitialized { sink } => sink,
@@ -106,6 +163,12 @@ impl Component for Admin {
                 };
                 true
             }
+            Right(Ok(Right(update))) => {
+                if let Self::Initialized { log, .. } = self {
+                    *log = update.songs;
+                }
+                true
+            }
             // TODO: handle closed connection properly
             Right(Err(_)) => false,
         }
@@ -113,24 +176,164 @@ impl Component for Admin {
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         match self {
-            Self::Uninitialized { .. } => html! { "Ich warte auf den Server" },
+            Self::Uninitialized { .. } => html! { Text::WaitingForServer.get() },
             Self::Initialized {
-                phase, teams, song, ..
+                phase,
+                teams,
+                queue_buzzes,
+                song,
+                library,
+                queue,
+                rules,
+                buzzes,
+                answer_time,
+                listening_time,
+                rounds,
+                round,
+                played,
+                name_packs,
+                name_pack,
+                approve_names,
+                time_left,
+                log,
+                ..
             } => {
                 let callback = ctx
                     .link()
                     .callback(|interaction: AdminInteraction| Left(interaction));
                 let current = match phase {
-                    Phase::Selection => html! { <Selection callback={callback.clone()}/> },
+                    Phase::Selection => {
+                        html! { <Selection callback={callback.clone()} library={library.clone()}/> }
+                    }
                     Phase::Listening { .. } => {
                         html! { <Listening callback={callback.clone()} track={song.clone()}/> }
                     }
-                    Phase::Guessing { .. } => html! { <Guessing callback={callback.clone()}/> },
+                    Phase::Guessing { team, solved, .. } => {
+                        let name = teams
+                            .iter()
+                            .find(|entry| entry.id == *team)
+                            .map_or("", |entry| entry.name.as_str());
+                        let player = buzzes
+                            .iter()
+                            .find(|buzz| buzz.team == *team)
+                            .and_then(|buzz| buzz.player.as_deref());
+                        html! {
+                            <>
+                                <div class="container">{buzzed(name, player)}</div>
+                                { time_left.map_or(html! {}, |millis| html! { <Countdown {millis}/> }) }
+                                <Guessing callback={callback.clone()} solved={*solved}/>
+                                <Buzzes buzzes={buzzes.clone()} teams={teams.clone()}/>
+                            </>
+                        }
+                    }
+                    Phase::RoundOver { round } => html! {
+                        <div class="container">{round_over(&rounds[*round].name)}</div>
+                    },
+                    Phase::Finished => html! {
+                        <div class="container">{Text::MatchOver.get()}</div>
+                    },
+                };
+                let undo = {
+                    let callback = callback.clone();
+                    move |_| {
+                        callback.emit(AdminInteraction::Undo);
+                    }
+                };
+                let toggle_queue = {
+                    let callback = callback.clone();
+                    let enabled = !*queue_buzzes;
+                    move |_| {
+                        callback.emit(AdminInteraction::SetBuzzQueue { enabled });
+                    }
+                };
+                let toggle_approval = {
+                    let callback = callback.clone();
+                    let enabled = !*approve_names;
+                    move |_| {
+                        callback.emit(AdminInteraction::SetNameApproval { enabled });
+                    }
+                };
+                let set_answer_time = {
+                    let callback = callback.clone();
+                    move |event: Event| {
+                        let target = event.target().unwrap_throw();
+                        let input = target.dyn_into::<HtmlInputElement>().ok().unwrap_throw();
+                        let seconds = input.value().parse::<u64>().ok().filter(|seconds| *seconds > 0);
+                        let millis = seconds.map(|seconds| seconds.saturating_mul(1000));
+                        callback.emit(AdminInteraction::SetAnswerTime { millis });
+                    }
+                };
+                let set_listening_time = {
+                    let callback = callback.clone();
+                    move |event: Event| {
+                        let target = event.target().unwrap_throw();
+                        let input = target.dyn_into::<HtmlInputElement>().ok().unwrap_throw();
+                        let seconds = input.value().parse::<u64>().ok().filter(|seconds| *seconds > 0);
+                        let millis = seconds.map(|seconds| seconds.saturating_mul(1000));
+                        callback.emit(AdminInteraction::SetListeningTime { millis });
+                    }
+                };
+                let room = ctx.props().room.clone();
+                let close_room = {
+                    let room = room.clone();
+                    move |_| {
+                        let room = room.clone();
+                        spawn_local(async move {
+                            match Request::delete(&format!("/rooms/{room}")).send().await {
+                                Ok(response) if response.ok() => {
+                                    let _ = window().location().set_href("/admin");
+                                }
+                                _ => error!("Failed to close room"),
+                            }
+                        });
+                    }
                 };
                 html! {
                     <div class="admin-container">
-                        <Teams callback={callback} phase={phase.clone()} teams={teams.clone()}/>
+                        <div class="admin-header container">
+                            <span class="admin-room">
+                                {Text::Room.get()}{" "}<span class="admin-room-code">{room.clone()}</span>
+                            </span>
+                            <a class="admin-room-link" href={format!("/{room}/buzzer")}>{Text::Buzzer.get()}</a>
+                            <a class="admin-room-link" href={format!("/{room}/spectator")}>{Text::Spectators.get()}</a>
+                            <a class="admin-room-link" href={format!("/export/results.csv?room={room}")}>{"CSV"}</a>
+                            <a class="admin-room-link" href={format!("/export/results.json?room={room}")}>{"JSON"}</a>
+                            <button class="admin-close-room" onclick={close_room}>{Text::CloseRoom.get()}</button>
+                            <label class="admin-buzz-queue">
+                                <input type="checkbox" checked={*queue_buzzes} onchange={toggle_queue}/>
+                                {Text::BuzzQueue.get()}
+                            </label>
+                            <label class="admin-approve-names">
+                                <input type="checkbox" checked={*approve_names} onchange={toggle_approval}/>
+                                {Text::ApproveNames.get()}
+                            </label>
+                            <label class="admin-answer-time">
+                                {Text::AnswerTime.get()}{" "}
+                                <input class="admin-answer-time-input" type="number" min="0"
+                                    value={answer_time.map(|millis| (millis / 1000).to_string()).unwrap_or_default()}
+                                    onchange={set_answer_time}/>
+                                {" s"}
+                            </label>
+                            <label class="admin-listening-time">
+                                {Text::ListeningTime.get()}{" "}
+                                <input class="admin-listening-time-input" type="number" min="0"
+                                    value={listening_time.map(|millis| (millis / 1000).to_string()).unwrap_or_default()}
+                                    onchange={set_listening_time}/>
+                                {" s"}
+                            </label>
+                            <span class="admin-undo">
+                                <button class="admin-undo-button" onclick={undo}>{"undo"}</button>
+                                <span class="admin-undo-text">{Text::Undo.get()}</span>
+                            </span>
+                        </div>
+                        <Teams callback={callback.clone()} phase={phase.clone()} teams={teams.clone()}
+                            name_packs={name_packs.clone()} name_pack={name_pack.clone()}/>
+                        <Rounds callback={callback.clone()} phase={phase.clone()} rounds={rounds.clone()}
+                            round={*round} played={*played}/>
                         { current }
+                        <Queue callback={callback.clone()} phase={phase.clone()} queue={queue.clone()}/>
+                        <Scoring callback={callback} rules={rules.clone()}/>
+                        <Log songs={log.clone()} rounds={rounds.clone()}/>
                     </div>
                 }
             }
@@ -0,0 +1,91 @@
+use types::{game::Phase, message::AdminInteraction, track::LibraryEntry};
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use web_sys::Element;
+use yew::prelude::*;
+
+use crate::i18n::Text;
+
+#[derive(Debug, Clone, Properties, PartialEq)]
+pub struct Properties {
+    pub callback: Callback<AdminInteraction>,
+    pub phase: Phase,
+    pub queue: Vec<LibraryEntry>,
+}
+
+fn event_index(event: &MouseEvent) -> usize {
+    let target = event.target().unwrap_throw();
+    let element = target.dyn_into::<Element>().unwrap_throw();
+    element
+        .get_attribute("data-index")
+        .unwrap_throw()
+        .parse::<usize>()
+        .ok()
+        .unwrap_throw()
+}
+
+#[function_component]
+pub fn Queue(props: &Properties) -> Html {
+    if props.queue.is_empty() {
+        return html! {};
+    }
+    let last = props.queue.len() - 1;
+    let up = {
+        let callback = props.callback.clone();
+        move |event: MouseEvent| {
+            let index = event_index(&event);
+            callback.emit(AdminInteraction::MoveQueueEntry {
+                from: index,
+                to: index.saturating_sub(1),
+            });
+        }
+    };
+    let down = {
+        let callback = props.callback.clone();
+        move |event: MouseEvent| {
+            let index = event_index(&event);
+            callback.emit(AdminInteraction::MoveQueueEntry {
+                from: index,
+                to: (index + 1).min(last),
+            });
+        }
+    };
+    let remove = {
+        let callback = props.callback.clone();
+        move |event: MouseEvent| {
+            callback.emit(AdminInteraction::RemoveQueueEntry {
+                index: event_index(&event),
+            });
+        }
+    };
+    let entries: Html = props.queue.iter().enumerate().map(|(n, entry)| {
+        html! {
+            <li class="admin-queue-entry">
+                <button class="admin-queue-button" data-index={n.to_string()} onclick={up.clone()}>{"arrow_upward"}</button>
+                <button class="admin-queue-button" data-index={n.to_string()} onclick={down.clone()}>{"arrow_downward"}</button>
+                <button class="admin-queue-button" data-index={n.to_string()} onclick={remove.clone()}>{"delete"}</button>
+                <span class="admin-queue-name">{entry.track.name.clone()}</span>
+                <span class="song-artists">{" "}{entry.track.artists.join(", ")}</span>
+            </li>
+        }
+    }).collect();
+    let next = if let Phase::Selection = props.phase {
+        let callback = props.callback.clone();
+        let onclick = move |_| {
+            callback.emit(AdminInteraction::PlayNext);
+        };
+        html! {
+            <li class="admin-queue-next">
+                <button class="admin-queue-button" {onclick}>{"skip_next"}</button>
+                <span class="admin-queue-next-text">{Text::PlayNext.get()}</span>
+            </li>
+        }
+    } else {
+        html! {}
+    };
+    html! {
+        <ul class={classes!("admin-queue", "container")}>
+            { next }
+            { entries }
+        </ul>
+    }
+}
@@ -0,0 +1,174 @@
+use types::{
+    game::{Phase, Round},
+    message::AdminInteraction,
+};
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::i18n::{round_of, songs, Text};
+
+#[derive(Debug, Clone, Properties, PartialEq)]
+pub struct Properties {
+    pub callback: Callback<AdminInteraction>,
+    pub phase: Phase,
+    pub rounds: Vec<Round>,
+    pub round: usize,
+    pub played: usize,
+}
+
+/// The input element with the given id.
+fn input(id: &str) -> HtmlInputElement {
+    gloo::utils::document()
+        .get_element_by_id(id)
+        .unwrap_throw()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap_throw()
+}
+
+/// Plans the rounds of a match and advances through them.
+#[function_component]
+pub fn Rounds(props: &Properties) -> Html {
+    let planned = use_state(Vec::<Round>::new);
+    let current = props.rounds.get(props.round);
+    if let Some(current) = current.filter(|_| props.phase != Phase::Finished) {
+        let next_round = {
+            let callback = props.callback.clone();
+            move |_| callback.emit(AdminInteraction::NextRound)
+        };
+        let finish = {
+            let callback = props.callback.clone();
+            move |_| callback.emit(AdminInteraction::FinishMatch)
+        };
+        let next = match props.phase {
+            Phase::Selection => html! {
+                <span class="admin-rounds-action">
+                    <button class="admin-rounds-button" onclick={next_round}>{"skip_next"}</button>
+                    {Text::EndRound.get()}
+                </span>
+            },
+            Phase::RoundOver { round } if round + 1 < props.rounds.len() => html! {
+                <span class="admin-rounds-action">
+                    <button class="admin-rounds-button" onclick={next_round}>{"skip_next"}</button>
+                    {Text::NextRound.get()}
+                </span>
+            },
+            _ => html! {},
+        };
+        let finish = match props.phase {
+            Phase::Selection | Phase::RoundOver { .. } => html! {
+                <span class="admin-rounds-action">
+                    <button class="admin-rounds-button" onclick={finish}>{"emoji_events"}</button>
+                    {Text::FinishMatch.get()}
+                </span>
+            },
+            _ => html! {},
+        };
+        return html! {
+            <div class={classes!("admin-rounds", "container")}>
+                <span class="admin-rounds-current">
+                    {format!(
+                        "{} – {}/{}",
+                        round_of(props.round + 1, props.rounds.len(), &current.name),
+                        props.played,
+                        songs(current.songs)
+                    )}
+                </span>
+                { next }
+                { finish }
+            </div>
+        };
+    }
+    let add = {
+        let planned = planned.clone();
+        move |_| {
+            let name = input("admin-rounds-name");
+            let songs = input("admin-rounds-songs");
+            let Ok(count) = songs.value().parse::<usize>() else {
+                return;
+            };
+            if name.value().is_empty() || count == 0 {
+                return;
+            }
+            let mut rounds = (*planned).clone();
+            rounds.push(Round {
+                name: name.value(),
+                songs: count,
+                points: Default::default(),
+            });
+            planned.set(rounds);
+            name.set_value("");
+        }
+    };
+    let start = {
+        let callback = props.callback.clone();
+        let planned = planned.clone();
+        move |_| {
+            callback.emit(AdminInteraction::StartMatch {
+                rounds: (*planned).clone(),
+            });
+            planned.set(Vec::new());
+        }
+    };
+    let remove = {
+        let planned = planned.clone();
+        move |event: MouseEvent| {
+            let target = event.target().unwrap_throw();
+            let element = target.dyn_into::<web_sys::Element>().unwrap_throw();
+            let index = element
+                .get_attribute("data-round")
+                .unwrap_throw()
+                .parse::<usize>()
+                .unwrap_throw();
+            let mut rounds = (*planned).clone();
+            rounds.remove(index);
+            planned.set(rounds);
+        }
+    };
+    let list: Html = planned
+        .iter()
+        .enumerate()
+        .map(|(n, round)| {
+            html! {
+                <li class="admin-rounds-entry">
+                    <button class="admin-rounds-button" data-round={n.to_string()} onclick={remove.clone()}>{"remove"}</button>
+                    {format!("{} ({})", round.name, songs(round.songs))}
+                </li>
+            }
+        })
+        .collect();
+    let leave = if props.phase == Phase::Finished {
+        let callback = props.callback.clone();
+        let leave = move |_| callback.emit(AdminInteraction::LeaveMatch);
+        html! {
+            <span class="admin-rounds-action">
+                <button class="admin-rounds-button" onclick={leave}>{"music_note"}</button>
+                {Text::LeaveMatch.get()}
+            </span>
+        }
+    } else {
+        html! {}
+    };
+    let start = if planned.is_empty() {
+        html! {}
+    } else {
+        html! {
+            <span class="admin-rounds-action">
+                <button class="admin-rounds-button" onclick={start}>{"flag"}</button>
+                {Text::StartMatch.get()}
+            </span>
+        }
+    };
+    html! {
+        <div class={classes!("admin-rounds", "container")}>
+            <ul class="admin-rounds-list">{ list }</ul>
+            <span class="admin-rounds-add">
+                <input id="admin-rounds-name" class="admin-rounds-input" placeholder={Text::Round.get()}/>
+                <input id="admin-rounds-songs" class="admin-rounds-input" type="number" min="1" value="5"/>
+                <button class="admin-rounds-button" onclick={add}>{"add"}</button>
+            </span>
+            { start }
+            { leave }
+        </div>
+    }
+}
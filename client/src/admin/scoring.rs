@@ -0,0 +1,115 @@
+use types::{
+    message::AdminInteraction,
+    scoring::{ScoringRules, PRESETS},
+};
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::i18n::Text;
+
+#[derive(Debug, Clone, Properties, PartialEq)]
+pub struct Properties {
+    pub callback: Callback<AdminInteraction>,
+    pub rules: ScoringRules,
+}
+
+const FIELDS: &[(&str, Text)] = &[
+    ("correct", Text::Correct),
+    ("title", Text::OnlyTitle),
+    ("artist", Text::OnlyArtist),
+    ("wrong", Text::Wrong),
+    ("fast_bonus", Text::FastBonus),
+    ("fast_window", Text::FastWindow),
+    ("decay", Text::Decay),
+    ("decay_interval", Text::DecayInterval),
+    ("minimum", Text::Minimum),
+];
+
+fn field(rules: &ScoringRules, name: &str) -> i64 {
+    match name {
+        "correct" => rules.correct,
+        "title" => rules.title,
+        "artist" => rules.artist,
+        "wrong" => rules.wrong,
+        "fast_bonus" => rules.fast_bonus,
+        "fast_window" => rules.fast_window as i64,
+        "decay" => rules.decay,
+        "decay_interval" => rules.decay_interval as i64,
+        "minimum" => rules.minimum,
+        _ => 0,
+    }
+}
+
+fn set_field(rules: &mut ScoringRules, name: &str, value: i64) {
+    match name {
+        "correct" => rules.correct = value,
+        "title" => rules.title = value,
+        "artist" => rules.artist = value,
+        "wrong" => rules.wrong = value,
+        "fast_bonus" => rules.fast_bonus = value,
+        "fast_window" => rules.fast_window = value.max(0) as u64,
+        "decay" => rules.decay = value,
+        "decay_interval" => rules.decay_interval = value.max(0) as u64,
+        "minimum" => rules.minimum = value,
+        _ => (),
+    }
+}
+
+#[function_component]
+pub fn Scoring(props: &Properties) -> Html {
+    let onpreset = {
+        let callback = props.callback.clone();
+        move |event: Event| {
+            let target = event.target().unwrap_throw();
+            let select = target.dyn_into::<HtmlSelectElement>().unwrap_throw();
+            if let Some(rules) = ScoringRules::preset(&select.value()) {
+                callback.emit(AdminInteraction::SetScoring { rules });
+            }
+        }
+    };
+    let onchange = {
+        let callback = props.callback.clone();
+        let rules = props.rules.clone();
+        move |event: Event| {
+            let target = event.target().unwrap_throw();
+            let input = target.dyn_into::<HtmlInputElement>().unwrap_throw();
+            let name = input.get_attribute("data-field").unwrap_throw();
+            if let Ok(value) = input.value().parse::<i64>() {
+                let mut rules = rules.clone();
+                set_field(&mut rules, &name, value);
+                callback.emit(AdminInteraction::SetScoring { rules });
+            }
+        }
+    };
+    let current = PRESETS
+        .iter()
+        .find(|name| ScoringRules::preset(name).as_ref() == Some(&props.rules));
+    let presets: Html = PRESETS
+        .iter()
+        .map(|name| {
+            html! { <option value={*name} selected={current == Some(name)}>{name}</option> }
+        })
+        .collect();
+    let fields: Html = FIELDS
+        .iter()
+        .map(|(name, label)| {
+            html! {
+                <label class="admin-scoring-field">
+                    <span class="admin-scoring-label">{label.get()}</span>
+                    <input type="number" class="admin-scoring-input" data-field={*name}
+                        value={field(&props.rules, name).to_string()} onchange={onchange.clone()}/>
+                </label>
+            }
+        })
+        .collect();
+    html! {
+        <div class={classes!("admin-scoring", "container")}>
+            <select class="admin-scoring-preset" onchange={onpreset}>
+                <option value="" selected={current.is_none()}>{Text::CustomRules.get()}</option>
+                { presets }
+            </select>
+            { fields }
+        </div>
+    }
+}
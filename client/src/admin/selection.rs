@@ -1,13 +1,16 @@
 use gloo::console::debug;
-use types::message::AdminInteraction;
+use types::{message::AdminInteraction, track::LibraryEntry};
 use url::Url;
 use wasm_bindgen::{JsCast, UnwrapThrowExt};
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
+use crate::i18n::Text;
+
 #[derive(Debug, Clone, Properties, PartialEq)]
 pub struct Properties {
     pub callback: Callback<AdminInteraction>,
+    pub library: Vec<LibraryEntry>,
 }
 
 fn spotify_link_to_uri(link: String) -> Option<String> {
@@ -19,20 +22,58 @@ fn spotify_link_to_uri(link: String) -> Option<String> {
         return None;
     }
     let mut segs = url.path_segments()?;
-    if segs.next() != Some("track") {
+    let kind = segs.next()?;
+    if !["track", "playlist", "album"].contains(&kind) {
         return None;
     }
     let id = segs.next()?;
     if segs.next() != None {
         return None;
     }
-    Some(format!("spotify:track:{}", id))
+    Some(format!("spotify:{}:{}", kind, id))
+}
+
+fn input_to_interaction(
+    input: String,
+    library: &[LibraryEntry],
+    start: Option<u64>,
+    duration: Option<u64>,
+) -> Option<AdminInteraction> {
+    if library.iter().any(|entry| entry.id == input) {
+        return Some(AdminInteraction::Selection {
+            id: input,
+            start,
+            duration,
+        });
+    }
+    let uri = spotify_link_to_uri(input)?;
+    if uri.starts_with("spotify:track:") {
+        Some(AdminInteraction::Selection {
+            id: uri,
+            start,
+            duration,
+        })
+    } else {
+        Some(AdminInteraction::LoadQueue { id: uri })
+    }
+}
+
+/// The number of seconds entered into the input with the given id, if any.
+fn seconds(id: &str) -> Option<u64> {
+    gloo::utils::document()
+        .get_element_by_id(id)?
+        .dyn_into::<HtmlInputElement>()
+        .ok()?
+        .value()
+        .parse()
+        .ok()
 }
 
 #[function_component]
 pub fn Selection(props: &Properties) -> Html {
     let onclick = {
         let callback = props.callback.clone();
+        let library = props.library.clone();
         move |_| {
             let document = gloo::utils::document();
             let input = document
@@ -40,17 +81,37 @@ pub fn Selection(props: &Properties) -> Html {
                 .unwrap_throw()
                 .dyn_into::<HtmlInputElement>()
                 .unwrap_throw();
-            if let Some(uri) = spotify_link_to_uri(input.value()) {
-                callback.emit(AdminInteraction::Selection { id: uri })
+            let start = seconds("selection-start").filter(|seconds| *seconds > 0);
+            let duration = seconds("selection-duration").filter(|seconds| *seconds > 0);
+            if let Some(interaction) = input_to_interaction(input.value(), &library, start, duration) {
+                callback.emit(interaction)
             } else {
                 debug!("Spotify link could not be parsed: {}", input.value());
             }
         }
     };
+    let library = props
+        .library
+        .iter()
+        .map(|entry| {
+            let label = format!("{} – {}", entry.track.artists.join(", "), entry.track.name);
+            html! { <option value={entry.id.clone()}>{label}</option> }
+        })
+        .collect::<Html>();
     html! {
         <div class={classes!("selection", "container")}>
             <button id="selection-button" {onclick}>{"play_arrow"}</button>
-            <input id="selection-input"/>
+            <input id="selection-input" list="selection-library"/>
+            <datalist id="selection-library">
+                { library }
+            </datalist>
+            <label class="selection-snippet">
+                {Text::SnippetFrom.get()}{" "}
+                <input id="selection-start" class="selection-snippet-input" type="number" min="0" placeholder="0"/>
+                {" s, "}{Text::SnippetFor.get()}{" "}
+                <input id="selection-duration" class="selection-snippet-input" type="number" min="0" placeholder="∞"/>
+                {" s"}
+            </label>
         </div>
     }
 }
@@ -1,16 +1,20 @@
 use types::{
-    game::{Phase, Team},
-    message::AdminInteraction,
+    game::{Phase, Team, TeamId},
+    message::{AdminInteraction, NamePack},
 };
 use wasm_bindgen::{JsCast, UnwrapThrowExt};
-use web_sys::{Element, HtmlInputElement};
+use web_sys::{Element, HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
 
+use crate::i18n::{player_stats, proposed, Text};
+
 #[derive(Debug, Clone, Properties, PartialEq)]
 pub struct Properties {
     pub callback: Callback<AdminInteraction>,
     pub phase: Phase,
     pub teams: Vec<Team>,
+    pub name_packs: Vec<NamePack>,
+    pub name_pack: Option<String>,
 }
 
 #[function_component]
@@ -23,7 +27,7 @@ pub fn Teams(props: &Properties) -> Html {
             let team = input
                 .get_attribute("data-team")
                 .unwrap_throw()
-                .parse::<usize>()
+                .parse::<TeamId>()
                 .ok()
                 .unwrap_throw();
             callback.emit(AdminInteraction::RenameTeam {
@@ -40,22 +44,141 @@ pub fn Teams(props: &Properties) -> Html {
             let team = element
                 .get_attribute("data-team")
                 .unwrap_throw()
-                .parse::<usize>()
+                .parse::<TeamId>()
                 .ok()
                 .unwrap_throw();
             callback.emit(AdminInteraction::DeleteTeam { team: team });
         }
     };
-    let teams: Html = props.teams.iter().enumerate().map(|(n, team)| {
-        let inactive = if props.phase.is_active(n) {None} else {Some("team-inactive")};
+    let onreroll = {
+        let callback = props.callback.clone();
+        move |event: MouseEvent| {
+            let target = event.target().unwrap_throw();
+            let element = target.dyn_into::<Element>().unwrap_throw();
+            let team = element
+                .get_attribute("data-team")
+                .unwrap_throw()
+                .parse::<TeamId>()
+                .ok()
+                .unwrap_throw();
+            callback.emit(AdminInteraction::RerollTeamName { team });
+        }
+    };
+    let onproposal = {
+        let callback = props.callback.clone();
+        move |event: MouseEvent| {
+            let target = event.target().unwrap_throw();
+            let element = target.dyn_into::<Element>().unwrap_throw();
+            let team = element
+                .get_attribute("data-team")
+                .unwrap_throw()
+                .parse::<TeamId>()
+                .ok()
+                .unwrap_throw();
+            match element.get_attribute("data-approve").as_deref() {
+                Some("true") => callback.emit(AdminInteraction::ApproveName { team }),
+                _ => callback.emit(AdminInteraction::RejectName { team }),
+            }
+        }
+    };
+    let onpoints = {
+        let callback = props.callback.clone();
+        move |event: Event| {
+            let target = event.target().unwrap_throw();
+            let input = target.dyn_into::<HtmlInputElement>().ok().unwrap_throw();
+            let team = input
+                .get_attribute("data-team")
+                .unwrap_throw()
+                .parse::<TeamId>()
+                .ok()
+                .unwrap_throw();
+            if let Ok(points) = input.value().parse::<i64>() {
+                callback.emit(AdminInteraction::SetPoints { team, points });
+            }
+        }
+    };
+    let onadjust = {
+        let callback = props.callback.clone();
+        move |event: MouseEvent| {
+            let target = event.target().unwrap_throw();
+            let element = target.dyn_into::<Element>().unwrap_throw();
+            let team = element
+                .get_attribute("data-team")
+                .unwrap_throw()
+                .parse::<TeamId>()
+                .ok()
+                .unwrap_throw();
+            let delta = element
+                .get_attribute("data-delta")
+                .unwrap_throw()
+                .parse::<i64>()
+                .ok()
+                .unwrap_throw();
+            callback.emit(AdminInteraction::AdjustPoints { team, delta });
+        }
+    };
+    let teams: Html = props.teams.iter().map(|team| {
+        let inactive = if props.phase.is_active(team.id) {None} else {Some("team-inactive")};
         html! {
             <li class="admin-team">
-                <button class="admin-team-remove" data-team={n.to_string()} onclick={onclick.clone()}>{"delete"}</button>
-                <input class={classes!("admin-team-name", inactive)} data-team={n.to_string()} value={team.name.clone()} onchange={onchange.clone()}/> 
-                <span class="admin-team-points">{team.points.to_string()}{ " Punkte" }</span>    
+                <button class="admin-team-remove" data-team={team.id.to_string()} onclick={onclick.clone()}>{"delete"}</button>
+                <input class={classes!("admin-team-name", inactive)} data-team={team.id.to_string()} value={team.name.clone()} onchange={onchange.clone()}/> 
+                <button class="admin-team-reroll" data-team={team.id.to_string()} title={Text::RerollName.get()} onclick={onreroll.clone()}>{"casino"}</button>
+                <button class="admin-team-points-button" data-team={team.id.to_string()} data-delta="-1" onclick={onadjust.clone()}>{"remove"}</button>
+                <input type="number" class="admin-team-points" data-team={team.id.to_string()} value={team.points.to_string()} onchange={onpoints.clone()}/>
+                <button class="admin-team-points-button" data-team={team.id.to_string()} data-delta="1" onclick={onadjust.clone()}>{"add"}</button>
+                <span class="admin-team-points-text">{ Text::PointsLabel.get() }</span>
+                { team.proposed.as_ref().map_or(html! {}, |name| html! {
+                    <div class="admin-team-proposal">
+                        {proposed(name)}
+                        <button class="admin-team-proposal-button" data-team={team.id.to_string()} data-approve="true" onclick={onproposal.clone()}>{"check"}</button>
+                        <button class="admin-team-proposal-button" data-team={team.id.to_string()} data-approve="false" onclick={onproposal.clone()}>{"close"}</button>
+                    </div>
+                }) }
+                <ul class="admin-team-players">
+                    { for team.players.iter().map(|player| html! {
+                        <li class="admin-team-player">
+                            <span class="admin-team-player-name">{player.name.clone()}</span>
+                            {" "}{player_stats(player.buzzes, player.correct)}
+                        </li>
+                    }) }
+                </ul>
             </li>
         }
     }).collect();
+    let packs = if props.name_packs.len() > 1 {
+        let callback = props.callback.clone();
+        let onchange = move |event: Event| {
+            let target = event.target().unwrap_throw();
+            let select = target.dyn_into::<HtmlSelectElement>().unwrap_throw();
+            callback.emit(AdminInteraction::SetNamePack {
+                pack: Some(select.value()),
+            });
+        };
+        let chosen = props
+            .name_pack
+            .clone()
+            .unwrap_or_else(|| props.name_packs[0].name.clone());
+        let options: Html = props
+            .name_packs
+            .iter()
+            .map(|pack| {
+                html! {
+                    <option value={pack.name.clone()} selected={pack.name == chosen}>
+                        {format!("{} ({})", pack.name, pack.language)}
+                    </option>
+                }
+            })
+            .collect();
+        html! {
+            <label class="admin-team-pack">
+                {Text::NamePack.get()}{" "}
+                <select class="admin-team-pack-select" {onchange}>{ options }</select>
+            </label>
+        }
+    } else {
+        html! {}
+    };
     let add = {
         let callback = props.callback.clone();
         let onclick = move |_| {
@@ -64,7 +187,8 @@ pub fn Teams(props: &Properties) -> Html {
         html! {
             <li class="admin-team-add">
                 <button class="admin-team-add-button" {onclick}>{ "create" }</button>
-                <span class="admin-team-add-text">{"Team hinzufügen"}</span>
+                <span class="admin-team-add-text">{Text::AddTeam.get()}</span>
+                { packs }
             </li>
         }
     };
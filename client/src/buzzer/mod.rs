@@ -1,28 +1,66 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use either::Either::{self, Left, Right};
-use futures::{lock::Mutex, stream::SplitSink, SinkExt, StreamExt};
+use futures::{lock::Mutex, stream, stream::SplitSink, SinkExt, StreamExt};
 use gloo::{
     console::error,
     net::websocket::{futures::WebSocket, Message, WebSocketError},
+    storage::{LocalStorage, Storage},
+    utils::window,
 };
 use types::{
-    game::{Phase, Team},
+    game::{Phase, Team, TeamId},
     message::{BuzzerInteraction, BuzzerUpdate},
 };
 use wasm_bindgen::{JsCast, UnwrapThrowExt};
-use web_sys::Element;
-use yew::{platform::spawn_local, prelude::*};
+use web_sys::{Element, HtmlInputElement};
+use yew::{
+    platform::{spawn_local, time::sleep},
+    prelude::*,
+};
+
+use crate::{
+    countdown::Countdown,
+    i18n::{awaiting_approval, queue_position, Text},
+    progress::ListeningProgress,
+    RoomProperties,
+};
+
+/// The local storage key of the persistent buzzer token.
+const TOKEN_KEY: &str = "songquiz-buzzer-token";
+
+/// The local storage key of the nickname last entered.
+const NICKNAME_KEY: &str = "songquiz-nickname";
+
+/// The longest time to wait before reconnecting.
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// What went wrong with the connection to the server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connection {
+    /// The server sent a message which could not be understood.
+    Invalid,
+    /// The connection was closed.
+    Closed,
+    /// It is time to try connecting again.
+    Reconnect,
+}
 
 #[derive(Debug, Clone)]
 pub enum Buzzer {
     Uninitialized {
         sink: Arc<Mutex<SplitSink<WebSocket, Message>>>,
+        /// How often connecting failed in a row.
+        attempts: u32,
     },
     Initialized {
         phase: Phase,
         teams: Vec<Team>,
-        registered: Option<usize>,
+        registered: Option<TeamId>,
+        player: Option<String>,
+        time_left: Option<u64>,
+        listening_time: Option<u64>,
+        listening_left: Option<u64>,
         sink: Arc<Mutex<SplitSink<WebSocket, Message>>>,
     },
 }
@@ -30,23 +68,73 @@ pub enum Buzzer {
 impl Buzzer {
     fn message_to_update(
         message: Result<Message, WebSocketError>,
-    ) -> Either<BuzzerInteraction, Result<BuzzerUpdate, bool>> {
+    ) -> Either<BuzzerInteraction, Result<BuzzerUpdate, Connection>> {
         match message {
-            Err(_) => Right(Err(false)),
             Ok(Message::Text(message)) => {
                 if let Ok(update) = serde_json::from_str::<BuzzerUpdate>(&message) {
                     Right(Ok(update))
                 } else {
-                    Right(Err(true))
+                    Right(Err(Connection::Invalid))
                 }
             }
-            Ok(_) => Right(Err(true)),
+            // Errors are followed by the end of the stream,
+            // which is handled as a closed connection.
+            _ => Right(Err(Connection::Invalid)),
         }
     }
 
+    /// The token identifying this buzzer across connections.
+    ///
+    /// It is generated on first use and kept in local storage.
+    fn token() -> String {
+        if let Ok(token) = LocalStorage::get::<String>(TOKEN_KEY) {
+            return token;
+        }
+        // Unlike random UUIDs, random values are also available
+        // when the page is not served over HTTPS.
+        let mut bytes = [0u8; 16];
+        window()
+            .crypto()
+            .unwrap_throw()
+            .get_random_values_with_u8_array(&mut bytes)
+            .unwrap_throw();
+        let token: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+        if LocalStorage::set(TOKEN_KEY, &token).is_err() {
+            error!("Failed to store buzzer token");
+        }
+        token
+    }
+
+    /// Connect to the room and feed its updates to the component.
+    fn connect(ctx: &Context<Self>) -> Arc<Mutex<SplitSink<WebSocket, Message>>> {
+        let url = format!("/ws/{}/buzzer?token={}", ctx.props().room, Self::token());
+        let socket = WebSocket::open(&url).unwrap_throw();
+        let (sink, stream) = socket.split();
+        let closed = stream::once(async { Right(Err(Connection::Closed)) });
+        ctx.link()
+            .send_stream(stream.map(Self::message_to_update).chain(closed));
+        Arc::new(Mutex::new(sink))
+    }
+
+    /// The trimmed value of the input element with the given id.
+    fn input_value(id: &str) -> String {
+        gloo::utils::document()
+            .get_element_by_id(id)
+            .and_then(|element| element.dyn_into::<HtmlInputElement>().ok())
+            .map(|input| input.value().trim().to_string())
+            .unwrap_or_default()
+    }
+
+    /// How long to wait before the given reconnection attempt.
+    fn backoff(attempts: u32) -> Duration {
+        Duration::from_millis(500)
+            .saturating_mul(2u32.saturating_pow(attempts))
+            .min(MAX_BACKOFF)
+    }
+
     fn send_interaction(&self, interaction: BuzzerInteraction) {
         let sink = match self {
-            Self::Uninitialized { sink } => Arc::clone(sink),
+            Self::Uninitialized { sink, .. } => Arc::clone(sink),
             Self::Initialized { sink, .. } => Arc::clone(sink),
         };
         spawn_local(async move {
@@ -65,19 +153,17 @@ impl Buzzer {
 }
 
 impl Component for Buzzer {
-    type Message = Either<BuzzerInteraction, Result<BuzzerUpdate, bool>>;
-    type Properties = ();
+    type Message = Either<BuzzerInteraction, Result<BuzzerUpdate, Connection>>;
+    type Properties = RoomProperties;
 
     fn create(ctx: &Context<Self>) -> Self {
-        let socket = WebSocket::open("ws/buzzer").unwrap_throw();
-        let (sink, stream) = socket.split();
-        ctx.link().send_stream(stream.map(Self::message_to_update));
         Self::Uninitialized {
-            sink: Arc::new(Mutex::new(sink)),
+            sink: Self::connect(ctx),
+            attempts: 0,
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Left(interaction) => {
                 self.send_interaction(interaction);
@@ -85,11 +171,15 @@ impl Component for Buzzer {
             }
             Right(Ok(update)) => {
                 match self {
-                    Self::Uninitialized { sink } => {
+                    Self::Uninitialized { sink, .. } => {
                         *self = Self::Initialized {
                             phase: update.phase,
                             teams: update.teams,
                             registered: update.registered,
+                            player: update.player,
+                            time_left: update.time_left,
+                            listening_time: update.listening_time,
+                            listening_left: update.listening_left,
                             sink: Arc::clone(sink),
                         };
                     }
@@ -98,14 +188,39 @@ impl Component for Buzzer {
                             phase: update.phase,
                             teams: update.teams,
                             registered: update.registered,
+                            player: update.player,
+                            time_left: update.time_left,
+                            listening_time: update.listening_time,
+                            listening_left: update.listening_left,
                             sink: Arc::clone(sink),
                         }
                     }
                 };
                 true
             }
-            // TODO: handle closed connection properly
-            Right(Err(_)) => false,
+            Right(Err(Connection::Invalid)) => false,
+            Right(Err(Connection::Closed)) => {
+                let (sink, attempts) = match self {
+                    Self::Uninitialized { sink, attempts } => (Arc::clone(sink), *attempts),
+                    Self::Initialized { sink, .. } => (Arc::clone(sink), 0),
+                };
+                let backoff = Self::backoff(attempts);
+                ctx.link().send_future(async move {
+                    sleep(backoff).await;
+                    Right(Err(Connection::Reconnect))
+                });
+                *self = Self::Uninitialized {
+                    sink,
+                    attempts: attempts + 1,
+                };
+                true
+            }
+            Right(Err(Connection::Reconnect)) => {
+                if let Self::Uninitialized { sink, .. } = self {
+                    *sink = Self::connect(ctx);
+                }
+                false
+            }
         }
     }
 
@@ -114,14 +229,22 @@ impl Component for Buzzer {
             .link()
             .callback(|interaction: BuzzerInteraction| Left(interaction));
         match self {
-            Self::Uninitialized { .. } => html! { {"Ich warte auf den Server"} },
+            Self::Uninitialized { attempts: 0, .. } => html! { {Text::WaitingForServer.get()} },
+            Self::Uninitialized { .. } => {
+                html! { {Text::Reconnecting.get()} }
+            }
             Self::Initialized {
                 phase,
                 teams,
                 registered,
+                player,
+                time_left,
+                listening_time,
+                listening_left,
                 ..
-            } => match registered {
-                Some(team) => {
+            } => match registered.and_then(|id| teams.iter().find(|team| team.id == id)) {
+                Some(entry) => {
+                    let team = &entry.id;
                     let onclick = {
                         let callback = callback.clone();
                         move |_| {
@@ -133,10 +256,59 @@ impl Component for Buzzer {
                     } else {
                         Some("buzzer-inactive")
                     };
+                    let queued = match phase {
+                        Phase::Guessing { queue, .. } => queue.iter().position(|queued| queued == team),
+                        _ => None,
+                    };
+                    let queued = match queued {
+                        Some(position) => html! {
+                            <div class="buzzer-queued">{queue_position(position + 1)}</div>
+                        },
+                        None => html! {},
+                    };
+                    let countdown = match time_left {
+                        Some(millis) => html! { <Countdown millis={*millis}/> },
+                        None => html! {},
+                    };
+                    let progress = match listening_time.zip(*listening_left) {
+                        Some((total, left)) => html! { <ListeningProgress {left} {total}/> },
+                        None => html! {},
+                    };
+                    let proposal = match (phase, &entry.proposed) {
+                        (Phase::Selection, Some(proposed)) => html! {
+                            <div class="buzzer-proposal">{awaiting_approval(proposed)}</div>
+                        },
+                        (Phase::Selection, None) => {
+                            let onclick = {
+                                let callback = callback.clone();
+                                move |_| {
+                                    let name = Self::input_value("buzzer-proposal");
+                                    if !name.is_empty() {
+                                        callback.emit(BuzzerInteraction::ProposeName { name });
+                                    }
+                                }
+                            };
+                            html! {
+                                <div class="buzzer-proposal">
+                                    <input id="buzzer-proposal" class="buzzer-nickname"
+                                        placeholder={Text::TeamName.get()} maxlength="32"/>
+                                    <button class="buzzer-proposal-button" {onclick}>{Text::ProposeName.get()}</button>
+                                </div>
+                            }
+                        }
+                        _ => html! {},
+                    };
                     html! {
                         <div class={classes!("container", "buzzer-container")}>
-                            <div class="buzzer-team-name">{teams[*team].name.clone()}</div>
+                            <div class="buzzer-team-name">{entry.name.clone()}</div>
+                            { player.as_ref().map_or(html! {}, |player| html! {
+                                <div class="buzzer-player-name">{player.clone()}</div>
+                            }) }
+                            { countdown }
+                            { progress }
                             <button class={classes!("buzzer", inactive)} {onclick}></button>
+                            { queued }
+                            { proposal }
                         </div>
                     }
                 }
@@ -149,19 +321,52 @@ impl Component for Buzzer {
                             let team = element
                                 .get_attribute("data-team")
                                 .unwrap_throw()
-                                .parse::<usize>()
+                                .parse::<TeamId>()
                                 .ok()
                                 .unwrap_throw();
-                            callback.emit(BuzzerInteraction::Register { team: team });
+                            let nickname = Self::input_value("buzzer-nickname");
+                            if LocalStorage::set(NICKNAME_KEY, &nickname).is_err() {
+                                error!("Failed to store nickname");
+                            }
+                            let player = Some(nickname).filter(|nickname| !nickname.is_empty());
+                            callback.emit(BuzzerInteraction::Register { team, player });
                         }
                     };
-                    let teams = teams.iter().enumerate().map(|(n, team)| {
-                        html! { <li class={classes!("container", "margin-bottom")}><button class="buzzer-team-name" onclick={onclick.clone()} data-team={n.to_string()}>{team.name.clone()}</button></li> }
+                    let nickname = LocalStorage::get::<String>(NICKNAME_KEY).unwrap_or_default();
+                    let create = if *phase == Phase::Selection {
+                        let onclick = {
+                            let callback = callback.clone();
+                            move |_| {
+                                let name = Self::input_value("buzzer-new-team");
+                                if !name.is_empty() {
+                                    callback.emit(BuzzerInteraction::CreateTeam { name });
+                                }
+                            }
+                        };
+                        html! {
+                            <div class={classes!("container", "margin-bottom")}>
+                                <input id="buzzer-new-team" class="buzzer-nickname"
+                                    placeholder={Text::TeamName.get()} maxlength="32"/>
+                                <button class="buzzer-proposal-button" {onclick}>{Text::CreateOwnTeam.get()}</button>
+                            </div>
+                        }
+                    } else {
+                        html! {}
+                    };
+                    let teams = teams.iter().map(|team| {
+                        html! { <li class={classes!("container", "margin-bottom")}><button class="buzzer-team-name" onclick={onclick.clone()} data-team={team.id.to_string()}>{team.name.clone()}</button></li> }
                     }).collect::<Html>();
                     html! {
-                        <ul>
-                            { teams }
-                        </ul>
+                        <>
+                            <div class={classes!("container", "margin-bottom")}>
+                                <input id="buzzer-nickname" class="buzzer-nickname" value={nickname}
+                                    placeholder={Text::Nickname.get()} maxlength="24"/>
+                            </div>
+                            <ul>
+                                { teams }
+                            </ul>
+                            { create }
+                        </>
                     }
                 }
             },
@@ -0,0 +1,45 @@
+use types::game::{Buzz, Team};
+use yew::prelude::*;
+
+use crate::i18n::player_of;
+
+#[derive(Debug, Clone, Properties, PartialEq)]
+pub struct Properties {
+    pub buzzes: Vec<Buzz>,
+    pub teams: Vec<Team>,
+}
+
+/// The buzzes of the current buzz round, fastest first.
+#[function_component]
+pub fn Buzzes(props: &Properties) -> Html {
+    if props.buzzes.len() < 2 {
+        return html! {};
+    }
+    let buzzes: Html = props
+        .buzzes
+        .iter()
+        .filter_map(|buzz| Some((props.teams.iter().find(|team| team.id == buzz.team)?, buzz)))
+        .map(|(team, buzz)| {
+            let delay = buzz.delay;
+            let delay = if delay > 0 {
+                html! { <span class="buzz-delay">{format!(" +{delay} ms")}</span> }
+            } else {
+                html! {}
+            };
+            html! {
+                <li class="buzz">
+                    <span class="buzz-team">{match &buzz.player {
+                        Some(player) => player_of(player, &team.name),
+                        None => team.name.clone(),
+                    }}</span>
+                    { delay }
+                </li>
+            }
+        })
+        .collect();
+    html! {
+        <ol class={classes!("buzzes", "container")}>
+            { buzzes }
+        </ol>
+    }
+}
@@ -0,0 +1,34 @@
+use gloo::timers::callback::Interval;
+use yew::prelude::*;
+
+/// How often the countdown is updated in milliseconds.
+const TICK: u32 = 100;
+
+#[derive(Debug, Clone, Properties, PartialEq)]
+pub struct Properties {
+    /// How many milliseconds were left when the server sent them.
+    pub millis: u64,
+}
+
+/// The time the guessing team has left to answer.
+#[function_component]
+pub fn Countdown(props: &Properties) -> Html {
+    let left = use_state(|| props.millis);
+    {
+        let left = left.clone();
+        use_effect_with(props.millis, move |millis| {
+            left.set(*millis);
+            let mut current = *millis;
+            let interval = Interval::new(TICK, move || {
+                current = current.saturating_sub(TICK as u64);
+                left.set(current);
+            });
+            move || drop(interval)
+        });
+    }
+    let seconds = (*left).div_ceil(1000);
+    let urgent = if seconds <= 3 { Some("countdown-urgent") } else { None };
+    html! {
+        <div class={classes!("countdown", urgent)}>{seconds.to_string()}</div>
+    }
+}
@@ -0,0 +1,329 @@
+use std::cell::OnceCell;
+
+use gloo::utils::window;
+
+/// A language the client can be shown in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    German,
+    English,
+}
+
+impl Language {
+    /// The language of the given code, such as `de` or `en-US`.
+    fn from_code(code: &str) -> Option<Self> {
+        let primary = code.split(['-', '_']).next()?.to_lowercase();
+        match primary.as_str() {
+            "de" => Some(Self::German),
+            "en" => Some(Self::English),
+            _ => None,
+        }
+    }
+
+    /// The language requested by the `lang` query parameter,
+    /// else the one preferred by the browser.
+    ///
+    /// Falls back to German if neither is supported.
+    fn detect() -> Self {
+        let requested = window().location().search().ok().and_then(|search| {
+            search
+                .trim_start_matches('?')
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("lang="))
+                .and_then(Self::from_code)
+        });
+        let preferred = || {
+            window()
+                .navigator()
+                .language()
+                .and_then(|code| Self::from_code(&code))
+        };
+        requested.or_else(preferred).unwrap_or(Self::German)
+    }
+}
+
+thread_local! {
+    static LANGUAGE: OnceCell<Language> = const { OnceCell::new() };
+}
+
+/// The language the client is shown in.
+pub fn language() -> Language {
+    LANGUAGE.with(|language| *language.get_or_init(Language::detect))
+}
+
+/// A fixed text of the user interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Text {
+    WaitingForServer,
+    Reconnecting,
+    Password,
+    WrongPassword,
+    CreateRoom,
+    CloseRoom,
+    RoomCode,
+    Room,
+    Buzzer,
+    Spectators,
+    BuzzQueue,
+    AnswerTime,
+    ListeningTime,
+    Undo,
+    AddTeam,
+    RerollName,
+    NamePack,
+    PointsLabel,
+    StopSong,
+    PlayNext,
+    SnippetFrom,
+    SnippetFor,
+    Accept,
+    Reject,
+    OnlyTitle,
+    OnlyArtist,
+    CustomRules,
+    Correct,
+    Wrong,
+    FastBonus,
+    FastWindow,
+    Decay,
+    DecayInterval,
+    Minimum,
+    Round,
+    EndRound,
+    NextRound,
+    FinishMatch,
+    StartMatch,
+    LeaveMatch,
+    MatchOver,
+    History,
+    Buzzes,
+    TitleGuessed,
+    ArtistGuessed,
+    PreviouslyPlayed,
+    Nickname,
+    TeamName,
+    CreateOwnTeam,
+    ProposeName,
+    ApproveNames,
+}
+
+impl Text {
+    /// This text in the language the client is shown in.
+    pub fn get(self) -> &'static str {
+        match language() {
+            Language::German => german(self),
+            Language::English => english(self),
+        }
+    }
+}
+
+fn german(text: Text) -> &'static str {
+    match text {
+        Text::WaitingForServer => "Ich warte auf den Server",
+        Text::Reconnecting => "Verbindung verloren, ich verbinde mich neu",
+        Text::Password => "Passwort",
+        Text::WrongPassword => "Falsches Passwort",
+        Text::CreateRoom => "Raum erstellen",
+        Text::CloseRoom => "Raum schließen",
+        Text::RoomCode => "Raumcode",
+        Text::Room => "Raum",
+        Text::Buzzer => "Buzzer",
+        Text::Spectators => "Zuschauer",
+        Text::BuzzQueue => "Buzzer-Warteschlange",
+        Text::AnswerTime => "Antwortzeit",
+        Text::ListeningTime => "Hörzeit",
+        Text::Undo => "Rückgängig",
+        Text::AddTeam => "Team hinzufügen",
+        Text::RerollName => "Neuer Name",
+        Text::NamePack => "Namen",
+        Text::PointsLabel => "Punkte",
+        Text::StopSong => "Song stoppen",
+        Text::PlayNext => "Nächsten Song spielen",
+        Text::SnippetFrom => "ab",
+        Text::SnippetFor => "für",
+        Text::Accept => "Akzeptieren",
+        Text::Reject => "Ablehnen",
+        Text::OnlyTitle => "Nur Titel",
+        Text::OnlyArtist => "Nur Interpret",
+        Text::CustomRules => "Eigene Regeln",
+        Text::Correct => "Richtig",
+        Text::Wrong => "Falsch",
+        Text::FastBonus => "Schnellbonus",
+        Text::FastWindow => "Schnell bis (s)",
+        Text::Decay => "Abzug",
+        Text::DecayInterval => "Abzug alle (s)",
+        Text::Minimum => "Minimum",
+        Text::Round => "Runde",
+        Text::EndRound => "Runde beenden",
+        Text::NextRound => "Nächste Runde",
+        Text::FinishMatch => "Match beenden",
+        Text::StartMatch => "Match starten",
+        Text::LeaveMatch => "Frei weiterspielen",
+        Text::MatchOver => "Das Match ist vorbei",
+        Text::History => "Verlauf",
+        Text::Buzzes => "Buzzer",
+        Text::TitleGuessed => "Titel",
+        Text::ArtistGuessed => "Interpret",
+        Text::PreviouslyPlayed => "Zuvor gespielt",
+        Text::Nickname => "Dein Name (optional)",
+        Text::TeamName => "Teamname",
+        Text::CreateOwnTeam => "Team erstellen",
+        Text::ProposeName => "Namen vorschlagen",
+        Text::ApproveNames => "Namen freigeben",
+    }
+}
+
+fn english(text: Text) -> &'static str {
+    match text {
+        Text::WaitingForServer => "Waiting for the server",
+        Text::Reconnecting => "Connection lost, reconnecting",
+        Text::Password => "Password",
+        Text::WrongPassword => "Wrong password",
+        Text::CreateRoom => "Create room",
+        Text::CloseRoom => "Close room",
+        Text::RoomCode => "Room code",
+        Text::Room => "Room",
+        Text::Buzzer => "Buzzer",
+        Text::Spectators => "Spectators",
+        Text::BuzzQueue => "Buzzer queue",
+        Text::AnswerTime => "Answer time",
+        Text::ListeningTime => "Listening time",
+        Text::Undo => "Undo",
+        Text::AddTeam => "Add team",
+        Text::RerollName => "New name",
+        Text::NamePack => "Names",
+        Text::PointsLabel => "Points",
+        Text::StopSong => "Stop song",
+        Text::PlayNext => "Play next song",
+        Text::SnippetFrom => "from",
+        Text::SnippetFor => "for",
+        Text::Accept => "Accept",
+        Text::Reject => "Reject",
+        Text::OnlyTitle => "Title only",
+        Text::OnlyArtist => "Artist only",
+        Text::CustomRules => "Custom rules",
+        Text::Correct => "Correct",
+        Text::Wrong => "Wrong",
+        Text::FastBonus => "Speed bonus",
+        Text::FastWindow => "Fast within (s)",
+        Text::Decay => "Decay",
+        Text::DecayInterval => "Decay every (s)",
+        Text::Minimum => "Minimum",
+        Text::Round => "Round",
+        Text::EndRound => "End round",
+        Text::NextRound => "Next round",
+        Text::FinishMatch => "Finish match",
+        Text::StartMatch => "Start match",
+        Text::LeaveMatch => "Continue freely",
+        Text::MatchOver => "The match is over",
+        Text::History => "History",
+        Text::Buzzes => "Buzzes",
+        Text::TitleGuessed => "title",
+        Text::ArtistGuessed => "artist",
+        Text::PreviouslyPlayed => "Previously played",
+        Text::Nickname => "Your name (optional)",
+        Text::TeamName => "Team name",
+        Text::CreateOwnTeam => "Create team",
+        Text::ProposeName => "Propose name",
+        Text::ApproveNames => "Approve names",
+    }
+}
+
+/// The given number of points, e.g. `1 Punkt` or `3 points`.
+pub fn points(points: i64) -> String {
+    let singular = points.abs() == 1;
+    match (language(), singular) {
+        (Language::German, true) => format!("{points} Punkt"),
+        (Language::German, false) => format!("{points} Punkte"),
+        (Language::English, true) => format!("{points} point"),
+        (Language::English, false) => format!("{points} points"),
+    }
+}
+
+/// The given number of songs.
+pub fn songs(songs: usize) -> String {
+    match (language(), songs == 1) {
+        (Language::German, true) => "1 Song".to_string(),
+        (Language::German, false) => format!("{songs} Songs"),
+        (Language::English, true) => "1 song".to_string(),
+        (Language::English, false) => format!("{songs} songs"),
+    }
+}
+
+/// That the given team, or a player of it, has pressed its buzzer.
+pub fn buzzed(team: &str, player: Option<&str>) -> String {
+    match (language(), player) {
+        (Language::German, None) => format!("{team} haben den Buzzer gedrückt"),
+        (Language::German, Some(player)) => format!("{} hat den Buzzer gedrückt", player_of(player, team)),
+        (Language::English, None) => format!("{team} pressed the buzzer"),
+        (Language::English, Some(player)) => format!("{} buzzed", player_of(player, team)),
+    }
+}
+
+/// A player named along with their team, e.g. `Anna (Coole Otter)`.
+pub fn player_of(player: &str, team: &str) -> String {
+    format!("{player} ({team})")
+}
+
+/// How often a player buzzed and guessed correctly.
+pub fn player_stats(buzzes: u64, correct: u64) -> String {
+    match language() {
+        Language::German => format!("{buzzes}× gebuzzert, {correct}× richtig"),
+        Language::English => format!("{buzzes} buzzes, {correct} correct"),
+    }
+}
+
+/// The position of a team in the buzz queue, counting from one.
+pub fn queue_position(position: usize) -> String {
+    match language() {
+        Language::German => format!("Ihr seid als {position}. in der Warteschlange"),
+        Language::English => format!("You are number {position} in the queue"),
+    }
+}
+
+/// That the round with the given name is over.
+pub fn round_over(name: &str) -> String {
+    match language() {
+        Language::German => format!("Runde „{name}“ ist vorbei"),
+        Language::English => format!("Round “{name}” is over"),
+    }
+}
+
+/// Which round of how many is played, and its name.
+pub fn round_of(round: usize, rounds: usize, name: &str) -> String {
+    format!("{} {round}/{rounds}: {name}", Text::Round.get())
+}
+
+/// How many seconds into the song a team buzzed.
+pub fn after_seconds(seconds: f64) -> String {
+    match language() {
+        Language::German => format!("nach {seconds:.1} s"),
+        Language::English => format!("after {seconds:.1} s"),
+    }
+}
+
+/// A short verdict on a guess, naming what was accepted.
+pub fn verdict(title: bool, artist: bool) -> &'static str {
+    match (title, artist) {
+        (true, true) => Text::Correct.get(),
+        (true, false) => Text::TitleGuessed.get(),
+        (false, true) => Text::ArtistGuessed.get(),
+        (false, false) => Text::Wrong.get(),
+    }
+}
+
+/// That the given proposed name awaits the approval of an admin.
+pub fn awaiting_approval(name: &str) -> String {
+    match language() {
+        Language::German => format!("„{name}“ wartet auf Freigabe"),
+        Language::English => format!("“{name}” awaits approval"),
+    }
+}
+
+/// The name proposed for a team.
+pub fn proposed(name: &str) -> String {
+    match language() {
+        Language::German => format!("Vorschlag: {name}"),
+        Language::English => format!("Proposed: {name}"),
+    }
+}
@@ -1,29 +1,50 @@
 mod admin;
 mod buzzer;
+mod buzzes;
+mod countdown;
+mod i18n;
+mod lobby;
+mod login;
+mod progress;
 mod spectator;
 
 use admin::Admin;
 use buzzer::Buzzer;
+use lobby::Lobby;
+use login::Login;
 use spectator::Spectator;
 use wasm_bindgen::prelude::*;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
+/// The properties of every view inside a room.
+#[derive(Debug, Clone, Properties, PartialEq)]
+pub struct RoomProperties {
+    /// The join code of the room.
+    pub room: String,
+}
+
 #[derive(Clone, Routable, PartialEq)]
 enum Route {
+    #[at("/login")]
+    Login,
     #[at("/admin")]
-    Admin,
-    #[at("/buzzer")]
-    Buzzer,
-    #[at("/spectator")]
-    Spectator,
+    Lobby,
+    #[at("/:room/admin")]
+    Admin { room: String },
+    #[at("/:room/buzzer")]
+    Buzzer { room: String },
+    #[at("/:room/spectator")]
+    Spectator { room: String },
 }
 
 fn switch(route: Route) -> Html {
     match route {
-        Route::Admin => html! { <Admin/> },
-        Route::Buzzer => html! { <Buzzer/> },
-        Route::Spectator => html! { <Spectator/> },
+        Route::Login => html! { <Login/> },
+        Route::Lobby => html! { <Lobby/> },
+        Route::Admin { room } => html! { <Admin {room}/> },
+        Route::Buzzer { room } => html! { <Buzzer {room}/> },
+        Route::Spectator { room } => html! { <Spectator {room}/> },
     }
 }
 
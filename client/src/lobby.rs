@@ -0,0 +1,46 @@
+use gloo::{console::error, net::http::Request, utils::window};
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use web_sys::HtmlInputElement;
+use yew::{platform::spawn_local, prelude::*};
+
+use crate::i18n::Text;
+
+fn enter(room: &str) {
+    let _ = window()
+        .location()
+        .set_href(&format!("/{}/admin", room.trim().to_uppercase()));
+}
+
+/// The entry point of an admin, which creates a new
+/// room or joins an existing one.
+#[function_component]
+pub fn Lobby() -> Html {
+    let create = move |_| {
+        spawn_local(async move {
+            let room = match Request::post("/rooms").send().await {
+                Ok(response) if response.ok() => response.text().await.ok(),
+                _ => None,
+            };
+            match room {
+                Some(room) => enter(&room),
+                None => error!("Failed to create room"),
+            }
+        });
+    };
+    let join = move |event: Event| {
+        let target = event.target().unwrap_throw();
+        let input = target.dyn_into::<HtmlInputElement>().ok().unwrap_throw();
+        if !input.value().trim().is_empty() {
+            enter(&input.value());
+        }
+    };
+    html! {
+        <div class="lobby container">
+            <div class="lobby-create">
+                <button class="lobby-create-button" onclick={create}>{"add"}</button>
+                <span class="lobby-create-text">{Text::CreateRoom.get()}</span>
+            </div>
+            <input class="lobby-join" placeholder={Text::RoomCode.get()} onchange={join}/>
+        </div>
+    }
+}
@@ -0,0 +1,28 @@
+use gloo::utils::window;
+use yew::prelude::*;
+
+use crate::i18n::Text;
+
+/// The login form of an admin.
+///
+/// The form is posted to the server directly, which sets the
+/// session cookie and redirects to the admin page.
+#[function_component]
+pub fn Login() -> Html {
+    let failed = window()
+        .location()
+        .search()
+        .is_ok_and(|search| search.contains("failed"));
+    let error = if failed {
+        html! { <span class="login-error">{Text::WrongPassword.get()}</span> }
+    } else {
+        html! {}
+    };
+    html! {
+        <form class="login container" method="post" action="/login">
+            <input class="login-secret" type="password" name="secret" placeholder={Text::Password.get()}/>
+            <button class="login-button" type="submit">{"login"}</button>
+            { error }
+        </form>
+    }
+}
@@ -0,0 +1,41 @@
+use gloo::timers::callback::Interval;
+use yew::prelude::*;
+
+/// How often the progress bar is updated in milliseconds.
+const TICK: u32 = 100;
+
+#[derive(Debug, Clone, Properties, PartialEq)]
+pub struct Properties {
+    /// How many milliseconds were left when the server sent them.
+    pub left: u64,
+    /// How many milliseconds the song may be listened to in total.
+    pub total: u64,
+}
+
+/// How much of the listening time of the song is used up.
+#[function_component]
+pub fn ListeningProgress(props: &Properties) -> Html {
+    let left = use_state(|| props.left);
+    {
+        let left = left.clone();
+        use_effect_with(props.left, move |millis| {
+            left.set(*millis);
+            let mut current = *millis;
+            let interval = Interval::new(TICK, move || {
+                current = current.saturating_sub(TICK as u64);
+                left.set(current);
+            });
+            move || drop(interval)
+        });
+    }
+    let used = if props.total == 0 {
+        100.0
+    } else {
+        100.0 * (props.total.saturating_sub(*left)) as f64 / props.total as f64
+    };
+    html! {
+        <div class="listening-progress">
+            <div class="listening-progress-bar" style={format!("width: {used:.1}%")}></div>
+        </div>
+    }
+}
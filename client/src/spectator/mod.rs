@@ -1,16 +1,27 @@
+mod podium;
 mod teams;
 
 use futures::StreamExt;
 use gloo::net::websocket::{futures::WebSocket, Message, WebSocketError};
+use podium::Podium;
 use teams::Teams;
 use types::{
-    game::{Phase, Team},
+    game::{Buzz, Phase, Round, Team},
     message::SpectatorUpdate,
-    track::Track,
+    track::{Audio, Track},
 };
-use wasm_bindgen::UnwrapThrowExt;
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use web_sys::HtmlAudioElement;
 use yew::prelude::*;
 
+use crate::{
+    buzzes::Buzzes,
+    countdown::Countdown,
+    i18n::{buzzed, round_of, round_over, Text},
+    progress::ListeningProgress,
+    RoomProperties,
+};
+
 #[derive(Debug, Clone)]
 pub enum Spectator {
     Uninitialized,
@@ -18,6 +29,14 @@ pub enum Spectator {
         phase: Phase,
         teams: Vec<Team>,
         revealed: Option<Track>,
+        audio: Option<Audio>,
+        buzzes: Vec<Buzz>,
+        time_left: Option<u64>,
+        listening_time: Option<u64>,
+        listening_left: Option<u64>,
+        rounds: Vec<Round>,
+        round: usize,
+        previous: Vec<Track>,
     },
 }
 
@@ -37,14 +56,38 @@ impl Spectator {
             Ok(_) => Err(true),
         }
     }
+
+    /// Bring the audio element in line with the audio sent by the server.
+    fn sync_audio(audio: &Audio) {
+        let Some(element) = gloo::utils::document()
+            .get_element_by_id("spectator-audio")
+            .and_then(|element| element.dyn_into::<HtmlAudioElement>().ok())
+        else {
+            return;
+        };
+        // The element seeks whenever the track or its position changes,
+        // as a new track starts from zero even if two snippets in a row
+        // start at the same offset.
+        let position = audio.position as f64 / 1000.0;
+        let synced = format!("{position} {}", audio.url);
+        if element.get_attribute("data-synced").as_deref() != Some(&synced) {
+            element.set_current_time(position);
+            let _ = element.set_attribute("data-synced", &synced);
+        }
+        if audio.playing {
+            let _ = element.play();
+        } else {
+            let _ = element.pause();
+        }
+    }
 }
 
 impl Component for Spectator {
     type Message = Result<SpectatorUpdate, bool>;
-    type Properties = ();
+    type Properties = RoomProperties;
 
     fn create(ctx: &Context<Self>) -> Self {
-        let socket = WebSocket::open("ws/spectator").unwrap_throw();
+        let socket = WebSocket::open(&format!("/ws/{}/spectator", ctx.props().room)).unwrap_throw();
         ctx.link().send_stream(socket.map(Self::message_to_update));
         Self::Uninitialized
     }
@@ -56,6 +99,14 @@ impl Component for Spectator {
                     phase: update.phase,
                     teams: update.teams,
                     revealed: update.revealed,
+                    audio: update.audio,
+                    buzzes: update.buzzes,
+                    time_left: update.time_left,
+                    listening_time: update.listening_time,
+                    listening_left: update.listening_left,
+                    rounds: update.rounds,
+                    round: update.round,
+                    previous: update.previous,
                 };
                 true
             }
@@ -66,11 +117,19 @@ impl Component for Spectator {
 
     fn view(&self, _ctx: &Context<Self>) -> Html {
         match self {
-            Self::Uninitialized => html! { {"Ich warte auf den Server"} },
+            Self::Uninitialized => html! { {Text::WaitingForServer.get()} },
             Self::Initialized {
                 phase,
                 teams,
                 revealed,
+                audio,
+                buzzes,
+                time_left,
+                listening_time,
+                listening_left,
+                rounds,
+                round,
+                previous,
             } => {
                 let current = match phase {
                     Phase::Selection => {
@@ -89,19 +148,119 @@ impl Component for Spectator {
                         }
                     }
                     Phase::Listening { .. } => {
-                        html! {}
+                        let song = match &revealed {
+                            Some(song) => html! {
+                                <div class="song container">
+                                    <div class="song-info">
+                                        <div class="song-name">{song.name.clone()}</div>
+                                        <div class="song-artists">{song.artists.join(", ")}</div>
+                                    </div>
+                                </div>
+                            },
+                            None => html! {},
+                        };
+                        let progress = match listening_time.zip(*listening_left) {
+                            Some((total, left)) => html! { <ListeningProgress {left} {total}/> },
+                            None => html! {},
+                        };
+                        html! {
+                            <>
+                                { song }
+                                { progress }
+                            </>
+                        }
                     }
                     Phase::Guessing { team, .. } => {
-                        html! { <div class="container"> {teams[*team].name.clone()} {" haben den Buzzer gedrückt"} </div> }
+                        let name = teams
+                            .iter()
+                            .find(|entry| entry.id == *team)
+                            .map_or("", |entry| entry.name.as_str());
+                        let player = buzzes
+                            .iter()
+                            .find(|buzz| buzz.team == *team)
+                            .and_then(|buzz| buzz.player.as_deref());
+                        html! {
+                            <>
+                                <div class="container"> {buzzed(name, player)} </div>
+                                { time_left.map_or(html! {}, |millis| html! { <Countdown {millis}/> }) }
+                                <Buzzes buzzes={buzzes.clone()} teams={teams.clone()}/>
+                            </>
+                        }
+                    }
+                    Phase::RoundOver { round } => {
+                        let results: Html = teams
+                            .iter()
+                            .map(|team| {
+                                let points = rounds[*round].points.get(&team.id).copied().unwrap_or(0);
+                                html! {
+                                    <li class="round-result">
+                                        <span class="round-result-name">{team.name.clone()}</span>
+                                        <span class="round-result-points">{format!(" +{points}")}</span>
+                                    </li>
+                                }
+                            })
+                            .collect();
+                        html! {
+                            <div class={classes!("round-over", "container")}>
+                                <div class="round-over-name">{round_over(&rounds[*round].name)}</div>
+                                <ul class="round-results">{ results }</ul>
+                            </div>
+                        }
                     }
+                    Phase::Finished => html! { <Podium teams={teams.clone()}/> },
+                };
+                let current_round = match rounds.get(*round) {
+                    Some(current) if matches!(phase, Phase::Selection | Phase::Listening { .. } | Phase::Guessing { .. }) => html! {
+                        <div class={classes!("round-current", "container")}>
+                            {round_of(round + 1, rounds.len(), &current.name)}
+                        </div>
+                    },
+                    _ => html! {},
+                };
+                let previous = if previous.is_empty() || *phase == Phase::Finished {
+                    html! {}
+                } else {
+                    let songs: Html = previous
+                        .iter()
+                        .map(|song| {
+                            html! {
+                                <li class="previous-song">
+                                    <span class="previous-song-name">{song.name.clone()}</span>
+                                    <span class="previous-song-artists">{" – "}{song.artists.join(", ")}</span>
+                                </li>
+                            }
+                        })
+                        .collect();
+                    html! {
+                        <div class={classes!("previous", "container")}>
+                            <div class="previous-text">{Text::PreviouslyPlayed.get()}</div>
+                            <ul class="previous-songs">{ songs }</ul>
+                        </div>
+                    }
+                };
+                let audio = match audio {
+                    Some(audio) => html! { <audio id="spectator-audio" src={audio.url.clone()}/> },
+                    None => html! {},
                 };
                 html! {
                     <div class="spectator-container">
+                        { current_round }
                         <Teams phase={phase.clone()} teams={teams.clone()}/>
                         { current }
+                        { previous }
+                        { audio }
                     </div>
                 }
             }
         }
     }
+
+    fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
+        if let Self::Initialized {
+            audio: Some(audio), ..
+        } = self
+        {
+            Self::sync_audio(audio);
+        }
+    }
 }
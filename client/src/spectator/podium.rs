@@ -0,0 +1,51 @@
+use types::game::Team;
+use yew::prelude::*;
+
+use crate::i18n::points;
+
+#[derive(Debug, Clone, Properties, PartialEq)]
+pub struct Properties {
+    pub teams: Vec<Team>,
+}
+
+/// The final standings of a match.
+///
+/// The three best teams are shown on a podium, all
+/// others are listed below it.
+#[function_component]
+pub fn Podium(props: &Properties) -> Html {
+    let mut teams = props.teams.clone();
+    teams.sort_by_key(|team| std::cmp::Reverse(team.points));
+    let place = |n: usize| match teams.get(n) {
+        Some(team) => html! {
+            <div class={classes!("podium-place", format!("podium-place-{}", n + 1))}>
+                <div class="podium-name">{team.name.clone()}</div>
+                <div class="podium-points">{points(team.points)}</div>
+                <div class="podium-step">{(n + 1).to_string()}</div>
+            </div>
+        },
+        None => html! {},
+    };
+    let rest: Html = teams
+        .iter()
+        .enumerate()
+        .skip(3)
+        .map(|(n, team)| {
+            html! {
+                <li class="podium-rest-entry">
+                    {format!("{}. {} – {}", n + 1, team.name, points(team.points))}
+                </li>
+            }
+        })
+        .collect();
+    html! {
+        <div class={classes!("podium", "container")}>
+            <div class="podium-places">
+                { place(1) }
+                { place(0) }
+                { place(2) }
+            </div>
+            <ol class="podium-rest">{ rest }</ol>
+        </div>
+    }
+}
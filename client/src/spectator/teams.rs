@@ -1,6 +1,8 @@
 use types::game::{Phase, Team};
 use yew::prelude::*;
 
+use crate::i18n::points;
+
 #[derive(Debug, Clone, Properties, PartialEq)]
 pub struct Properties {
     pub phase: Phase,
@@ -12,9 +14,8 @@ pub fn Teams(props: &Properties) -> Html {
     let teams: Html = props
         .teams
         .iter()
-        .enumerate()
-        .map(|(n, team)| {
-            let inactive = if props.phase.is_active(n) {
+        .map(|team| {
+            let inactive = if props.phase.is_active(team.id) {
                 None
             } else {
                 Some("team-inactive")
@@ -22,7 +23,7 @@ pub fn Teams(props: &Properties) -> Html {
             html! {
                 <li class="team">
                     <span class={classes!("team-name", inactive)}>{team.name.clone()}</span>
-                    <span class="team-points">{" "} {team.points.to_string()} {" Punkte"}</span>
+                    <span class="team-points">{" "} {points(team.points)}</span>
                 </li>
             }
         })
@@ -1,157 +1,204 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use axum::{
     extract::{
         ws::{Message, WebSocket},
-        ConnectInfo, State, WebSocketUpgrade,
+        ConnectInfo, Path, State, WebSocketUpgrade,
     },
-    response::IntoResponse,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_extra::{
+    headers::{Cookie, UserAgent},
+    TypedHeader,
 };
-use axum_extra::{headers::UserAgent, TypedHeader};
-use log::{debug, info};
-use rspotify::{
-    model::TrackId,
-    prelude::{BaseClient, OAuthClient},
+use log::debug;
+use tokio::{
+    select,
+    sync::{broadcast::error::RecvError, RwLock},
 };
-use tokio::{select, sync::RwLock};
 use types::{
-    game::{Phase, Team},
-    message::{AdminInteraction, AdminUpdate},
+    game::Phase,
+    message::{AdminInteraction, AdminLog, AdminUpdate},
 };
 
-use crate::{
-    game::{Game, Update},
-    names::random_name,
-};
+use crate::{auth::Auth, game::Event, names::Names, quiz::Quiz, room::Rooms};
+
+/// The current time in seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+/// A random team name from the chosen name pack of the
+/// quiz, which no team has yet.
+pub(crate) fn new_name(names: &Names, quiz: &Quiz) -> String {
+    let taken: Vec<String> = quiz.game.teams.iter().map(|team| team.name.clone()).collect();
+    names.generate(quiz.game.name_pack.as_deref(), &taken)
+}
 
 pub async fn admin_upgrade(
-    State(state): State<Arc<RwLock<Game>>>,
+    State(rooms): State<Arc<Rooms>>,
+    State(auth): State<Arc<Auth>>,
+    Path(room): Path<String>,
+    cookie: Option<TypedHeader<Cookie>>,
     ws: WebSocketUpgrade,
     user_agent: Option<TypedHeader<UserAgent>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-) -> impl IntoResponse {
+) -> Response {
+    if !auth.verify(&cookie).await {
+        debug!("Rejecting unauthenticated admin connection from {:?}", addr);
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let Some(state) = rooms.get(&room).await else {
+        debug!("Rejecting admin connection from {:?} to unknown room {room}", addr);
+        return StatusCode::NOT_FOUND.into_response();
+    };
     debug!(
-        "Got admin connection from {:?} at {:?}, entering handler",
+        "Got admin connection from {:?} at {:?} for room {room}, entering handler",
         user_agent, addr
     );
-    ws.on_upgrade(move |socket| admin_handler(state, socket, addr))
+    let names = Arc::clone(&rooms.names);
+    ws.on_upgrade(move |socket| admin_handler(state, names, socket, addr))
+        .into_response()
 }
 
-async fn admin_handler(state: Arc<RwLock<Game>>, mut socket: WebSocket, addr: SocketAddr) {
+async fn admin_handler(
+    state: Arc<RwLock<Quiz>>,
+    names: Arc<Names>,
+    mut socket: WebSocket,
+    addr: SocketAddr,
+) {
     let mut receiver = {
-        let game = state.read().await;
-        let receiver = game.channel.subscribe();
-        let _ = game.channel.send(Default::default());
+        let quiz = state.read().await;
+        let receiver = quiz.channel.subscribe();
+        let _ = quiz.channel.send(Default::default());
         receiver
     };
+    // The log only changes with songs and guesses, so it is
+    // sent separately whenever it did.
+    let mut logged = None;
     loop {
         select! {
-            _ = receiver.recv() => {
+            update = receiver.recv() => {
+                if let Err(RecvError::Closed) = update {
+                    debug!("Internal channel closed, exiting handler");
+                    break;
+                }
                 let update = {
-                    let game = state.read().await;
+                    let quiz = state.read().await;
                     AdminUpdate {
-                        phase: game.phase.clone(),
-                        teams: game.teams.clone(),
-                        song: game.song.clone().map(|t| t.into())
+                        phase: quiz.game.phase.clone(),
+                        teams: quiz.game.teams.clone(),
+                        queue_buzzes: quiz.game.queue_buzzes,
+                        song: quiz.game.song.clone(),
+                        library: quiz.playback.library(),
+                        queue: quiz.game.queue.clone(),
+                        rules: quiz.game.rules.clone(),
+                        buzzes: quiz.game.buzzes(),
+                        answer_time: quiz.game.answer_time.map(|time| time.as_millis() as u64),
+                        listening_time: quiz.game.listening_time.map(|time| time.as_millis() as u64),
+                        rounds: quiz.game.rounds.clone(),
+                        round: quiz.game.round,
+                        played: quiz.game.played,
+                        name_packs: names.packs(),
+                        name_pack: quiz.game.name_pack.clone(),
+                        approve_names: quiz.game.approve_names,
+                        time_left: quiz.time_left().map(|time| time.as_millis() as u64),
                     }
                 };
                 if let Err(_) = socket.send(Message::text(serde_json::to_string(&update).unwrap())).await {
                     debug!("Connection closed by peer at {:?}, exiting handler", addr);
                     break;
                 };
+                let log = {
+                    let quiz = state.read().await;
+                    let version = quiz.game.log_version;
+                    (logged != Some(version)).then(|| (version, quiz.game.log.clone()))
+                };
+                if let Some((version, songs)) = log {
+                    let message = AdminLog { songs };
+                    if socket.send(Message::text(serde_json::to_string(&message).unwrap())).await.is_err() {
+                        debug!("Connection closed by peer at {:?}, exiting handler", addr);
+                        break;
+                    };
+                    logged = Some(version);
+                }
             },
             raw = socket.recv() => {
                 if let Some(Ok(Message::Text(message))) = raw {
                     if let Ok(interaction) = serde_json::from_str::<AdminInteraction>(&message) {
                         debug!("Got admin interaction {:?}", interaction);
-                        let mut game = state.write().await;
-                        match interaction {
-                            AdminInteraction::Selection { id } => if let Phase::Selection = game.phase {
-                                let id = match TrackId::from_uri(&id) {
-                                    Ok(id) => id,
-                                    Err(e) => {
-                                        debug!("Failed to construct Spotify ID from URI: {:?}", e);
-                                        continue;
-                                    }
+                        let mut quiz = state.write().await;
+                        let event = match interaction {
+                            AdminInteraction::Selection { id, start, duration } => {
+                                let Phase::Selection = quiz.game.phase else {
+                                    continue;
                                 };
-                                match game.spotify.track(id.clone(), None).await {
-                                    Ok(track) => {
-                                        if let Err(e) = game.spotify.start_uris_playback(vec![id.into()], None, None, None).await {
-                                            debug!("Failed to start playback: {:?}", e);
-                                            continue;
-                                        }
-                                        game.song.replace(track);
-                                        game.phase = Phase::Listening { active: game.teams.iter().map(|_| true).collect() };
-                                        let _ = game.channel.send(Default::default());
+                                match quiz.playback.track(&id).await {
+                                    Ok(track) => Event::Select {
+                                        id,
+                                        track,
+                                        start: start.map_or(Duration::ZERO, Duration::from_secs),
+                                        snippet: duration.map(Duration::from_secs),
+                                        at: now(),
                                     },
                                     Err(e) => {
                                         debug!("Failed to get track info: {:?}", e);
                                         continue;
                                     }
-                                };
-                            },
-                            AdminInteraction::CreateTeam => if let Phase::Selection = game.phase {
-                                let team = Team {
-                                    name: random_name(),
-                                    points: 0
-                                };
-                                info!("Team created: {:?}", &team);
-                                game.teams.push(team);
-                                let _ = game.channel.send(Default::default());
-                            },
-                            AdminInteraction::RenameTeam { team, name } => if let Phase::Selection = game.phase {
-                                if team < game.teams.len() {
-                                    let former = game.teams[team].clone();
-                                    game.teams[team].name = name;
-                                    info!("Team renamed: former={:?} current={:?}", former, game.teams[team]);
-                                    let _ = game.channel.send(Default::default());
                                 }
                             },
-                            AdminInteraction::DeleteTeam { team } => if let Phase::Selection = game.phase {
-                                if team < game.teams.len() {
-                                    let removed = game.teams.remove(team);
-                                    info!("Team removed: {:?}", removed);
-                                    let _ = game.channel.send(Update {
-                                        teams_invalidated: true,
-                                        ..Default::default()
-                                    });
-                                }
+                            AdminInteraction::CreateTeam => Event::CreateTeam { name: new_name(&names, &quiz) },
+                            AdminInteraction::RerollTeamName { team } => Event::RenameTeam {
+                                team,
+                                name: new_name(&names, &quiz),
                             },
-                            AdminInteraction::StopListening => if let Phase::Listening { .. } = game.phase {
-                                if let Err(e) = game.spotify.resume_playback(None, None).await {
-                                    debug!("Spotify returned error {e}, likely not authorized");
+                            AdminInteraction::SetNamePack { pack } => Event::SetNamePack { pack },
+                            AdminInteraction::SetNameApproval { enabled } => Event::SetNameApproval { enabled },
+                            AdminInteraction::ApproveName { team } => Event::ApproveName { team },
+                            AdminInteraction::RejectName { team } => Event::RejectName { team },
+                            AdminInteraction::RenameTeam { team, name } => Event::RenameTeam { team, name },
+                            AdminInteraction::DeleteTeam { team } => Event::DeleteTeam { team },
+                            AdminInteraction::StopListening => Event::StopListening,
+                            AdminInteraction::AcceptGuess => Event::Accept { title: true, artist: true },
+                            AdminInteraction::AcceptTitle => Event::Accept { title: true, artist: false },
+                            AdminInteraction::AcceptArtist => Event::Accept { title: false, artist: true },
+                            AdminInteraction::RejectGuess => Event::RejectGuess,
+                            AdminInteraction::LoadQueue { id } => match quiz.playback.tracks(&id).await {
+                                Ok(entries) => Event::Enqueue { entries },
+                                Err(e) => {
+                                    debug!("Failed to load tracks: {:?}", e);
+                                    continue;
                                 }
-                                game.phase = Phase::Selection;
-                                let _ = game.channel.send(Default::default());
                             },
-                            AdminInteraction::AcceptGuess => if let Phase::Guessing { team, .. } = game.phase {
-                                if let Err(e) = game.spotify.resume_playback(None, None).await {
-                                    debug!("Spotify returned error {e}, likely not authorized");
-                                }
-                                game.teams[team].points += 1;
-                                game.phase = Phase::Selection;
-                                let _ = game.channel.send(Default::default());
+                            AdminInteraction::MoveQueueEntry { from, to } => Event::MoveQueueEntry { from, to },
+                            AdminInteraction::RemoveQueueEntry { index } => Event::RemoveQueueEntry { index },
+                            AdminInteraction::PlayNext => Event::PlayNext { at: now() },
+                            AdminInteraction::SetScoring { rules } => Event::SetScoring { rules },
+                            AdminInteraction::AdjustPoints { team, delta } => Event::AdjustPoints { team, delta },
+                            AdminInteraction::SetPoints { team, points } => Event::SetPoints { team, points },
+                            AdminInteraction::Undo => Event::Undo,
+                            AdminInteraction::StartMatch { rounds } => Event::StartMatch { rounds },
+                            AdminInteraction::NextRound => Event::NextRound,
+                            AdminInteraction::FinishMatch => Event::FinishMatch,
+                            AdminInteraction::LeaveMatch => Event::LeaveMatch,
+                            AdminInteraction::SetBuzzQueue { enabled } => Event::SetBuzzQueue { enabled },
+                            AdminInteraction::SetAnswerTime { millis } => Event::SetAnswerTime {
+                                time: millis.map(Duration::from_millis),
                             },
-                            AdminInteraction::RejectGuess => if let Phase::Guessing { team, active } = &game.phase {
-                                if let Err(e) = game.spotify.resume_playback(None, None).await {
-                                    debug!("Spotify returned error {e}, likely not authorized");
-                                }
-                                let actives = active.iter().enumerate().map(|(n, toggle)| {
-                                    if n == *team {
-                                        false
-                                    } else {
-                                        *toggle
-                                    }
-                                }).collect::<Vec<bool>>();
-                                if actives.iter().any(|f| *f) {
-                                    game.phase = Phase::Listening { active: actives };
-                                } else {
-                                    game.phase = Phase::Selection;
-                                }
-                                let _ = game.channel.send(Default::default());
+                            AdminInteraction::SetListeningTime { millis } => Event::SetListeningTime {
+                                time: millis.map(Duration::from_millis),
                             },
                         };
-
+                        if let Err(e) = quiz.dispatch(event).await {
+                            debug!("Ignoring admin interaction: {e:#}");
+                        }
                     }
                 } else {
                     debug!("Connection closed by peer at {:?}, exiting handler", addr);
@@ -0,0 +1,169 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use axum_extra::{headers::Cookie, TypedHeader};
+use log::{debug, info};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use serde::Deserialize;
+use tokio::sync::RwLock;
+
+/// The name of the cookie holding an admin session.
+pub const SESSION_COOKIE: &str = "songquiz_session";
+
+/// How many characters generated secrets and session tokens have.
+const TOKEN_LENGTH: usize = 32;
+
+/// How long an admin stays logged in.
+const SESSION_TTL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Guards everything only an admin may do.
+///
+/// An admin logs in once with the shared secret and is
+/// identified by a session cookie afterwards, until the
+/// session expires.
+#[derive(Debug)]
+pub struct Auth {
+    secret: String,
+    /// When each session was started, by its token.
+    sessions: RwLock<HashMap<String, Instant>>,
+    /// How long sessions are valid.
+    ttl: Duration,
+}
+
+impl Auth {
+    /// Create a guard which admits admins knowing the given secret.
+    pub fn new(secret: String) -> Self {
+        Self {
+            secret,
+            sessions: Default::default(),
+            ttl: SESSION_TTL,
+        }
+    }
+
+    /// Create a guard with a randomly generated secret.
+    pub fn generate() -> Self {
+        Self::new(random_token())
+    }
+
+    /// The secret admins have to log in with.
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    /// Start a new session if the given secret is correct
+    /// and return its token.
+    pub async fn login(&self, secret: &str) -> Option<String> {
+        if !constant_time_eq(secret.as_bytes(), self.secret.as_bytes()) {
+            return None;
+        }
+        let session = random_token();
+        let mut sessions = self.sessions.write().await;
+        sessions.retain(|_, started| started.elapsed() < self.ttl);
+        sessions.insert(session.clone(), Instant::now());
+        Some(session)
+    }
+
+    /// Whether the given cookies belong to a logged in admin.
+    pub async fn verify(&self, cookie: &Option<TypedHeader<Cookie>>) -> bool {
+        let Some(session) = cookie
+            .as_ref()
+            .and_then(|TypedHeader(cookie)| cookie.get(SESSION_COOKIE))
+        else {
+            return false;
+        };
+        self.sessions
+            .read()
+            .await
+            .get(session)
+            .is_some_and(|started| started.elapsed() < self.ttl)
+    }
+}
+
+/// A random alphanumeric token.
+pub(crate) fn random_token() -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+/// Compare two byte strings without leaking the position
+/// of the first difference through timing.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[derive(Deserialize)]
+pub struct LoginForm {
+    secret: String,
+}
+
+/// Log an admin in and redirect to the admin page.
+pub async fn login(State(auth): State<Arc<Auth>>, Form(form): Form<LoginForm>) -> Response {
+    match auth.login(&form.secret).await {
+        Some(session) => {
+            info!("Admin logged in");
+            (
+                [(
+                    header::SET_COOKIE,
+                    format!(
+                        "{SESSION_COOKIE}={session}; Path=/; Max-Age={}; HttpOnly; SameSite=Strict",
+                        SESSION_TTL.as_secs()
+                    ),
+                )],
+                Redirect::to("/admin"),
+            )
+                .into_response()
+        }
+        None => {
+            debug!("Rejected admin login with wrong secret");
+            Redirect::to("/login?failed").into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+    use axum_extra::headers::{Header, HeaderMapExt};
+
+    use super::*;
+
+    fn cookie(session: &str) -> Option<TypedHeader<Cookie>> {
+        let value = HeaderValue::from_str(&format!("{SESSION_COOKIE}={session}")).unwrap();
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(Cookie::name(), value);
+        headers.typed_get().map(TypedHeader)
+    }
+
+    #[tokio::test]
+    async fn only_sessions_from_logins_are_admins() {
+        let auth = Auth::new("geheim".to_string());
+        assert_eq!(auth.login("falsch").await, None);
+        assert_eq!(auth.login("").await, None);
+        let session = auth.login("geheim").await.unwrap();
+        assert!(auth.verify(&cookie(&session)).await);
+        assert!(!auth.verify(&cookie("forged")).await);
+        assert!(!auth.verify(&None).await);
+    }
+
+    #[tokio::test]
+    async fn sessions_expire() {
+        let mut auth = Auth::new("geheim".to_string());
+        auth.ttl = Duration::ZERO;
+        let session = auth.login("geheim").await.unwrap();
+        assert!(!auth.verify(&cookie(&session)).await);
+        auth.login("geheim").await.unwrap();
+        assert_eq!(auth.sessions.read().await.len(), 1);
+    }
+}
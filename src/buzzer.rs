@@ -1,61 +1,156 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::{
+    body::Bytes,
     extract::{
         ws::{Message, WebSocket},
-        ConnectInfo, State, WebSocketUpgrade,
+        ConnectInfo, Path, Query, State, WebSocketUpgrade,
     },
-    response::IntoResponse,
+    http::StatusCode,
+    response::{IntoResponse, Response},
 };
 use axum_extra::{headers::UserAgent, TypedHeader};
 use log::{debug, info};
-use rspotify::prelude::OAuthClient;
-use tokio::{select, sync::RwLock};
+use serde::Deserialize;
+use tokio::{
+    select,
+    sync::{broadcast::error::RecvError, RwLock},
+    time::interval,
+};
 use types::{
-    game::Phase,
+    game::Team,
     message::{BuzzerInteraction, BuzzerUpdate},
 };
 
-use crate::game::Game;
+use crate::{admin::new_name, auth::random_token, game::Event, names::Names, quiz::Quiz, room::Rooms};
+
+/// How often the latency of a buzzer is measured.
+const PING_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Estimates the one-way latency of a connection from the
+/// round trip times of pings.
+#[derive(Debug, Default)]
+struct Latency {
+    sequence: u64,
+    pinged: Option<(u64, Instant)>,
+    estimate: Option<Duration>,
+}
+
+impl Latency {
+    /// The payload of the next ping.
+    fn ping(&mut self) -> Bytes {
+        self.sequence += 1;
+        self.pinged = Some((self.sequence, Instant::now()));
+        Bytes::copy_from_slice(&self.sequence.to_be_bytes())
+    }
+
+    /// Account for a pong with the given payload.
+    ///
+    /// The estimate is smoothed, so a single slow round trip
+    /// does not skew it too much.
+    fn pong(&mut self, payload: &[u8]) {
+        let Some((sequence, sent)) = self.pinged else {
+            return;
+        };
+        if payload != sequence.to_be_bytes() {
+            return;
+        }
+        self.pinged = None;
+        let sample = sent.elapsed() / 2;
+        self.estimate = Some(match self.estimate {
+            Some(estimate) => (estimate * 3 + sample) / 4,
+            None => sample,
+        });
+    }
+
+    /// The current estimate, zero if nothing was measured yet.
+    fn estimate(&self) -> Duration {
+        self.estimate.unwrap_or_default()
+    }
+}
+
+/// The query of a buzzer connection.
+#[derive(Debug, Deserialize)]
+pub struct BuzzerQuery {
+    /// The persistent token identifying the buzzer.
+    token: Option<String>,
+}
 
 pub async fn buzzer_upgrade(
-    State(state): State<Arc<RwLock<Game>>>,
+    State(rooms): State<Arc<Rooms>>,
+    Path(room): Path<String>,
+    Query(query): Query<BuzzerQuery>,
     ws: WebSocketUpgrade,
     user_agent: Option<TypedHeader<UserAgent>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-) -> impl IntoResponse {
+) -> Response {
+    let Some(state) = rooms.get(&room).await else {
+        debug!("Rejecting buzzer connection from {:?} to unknown room {room}", addr);
+        return StatusCode::NOT_FOUND.into_response();
+    };
     debug!(
-        "Got buzzer connection from {:?} at {:?}, entering handler",
+        "Got buzzer connection from {:?} at {:?} for room {room}, entering handler",
         user_agent, addr
     );
-    ws.on_upgrade(move |socket| buzzer_handler(state, socket, addr))
+    // Buzzers without a token only keep their team for this connection.
+    let token = query.token.unwrap_or_else(random_token);
+    let names = Arc::clone(&rooms.names);
+    ws.on_upgrade(move |socket| buzzer_handler(state, names, socket, addr, token))
+        .into_response()
 }
 
-async fn buzzer_handler(state: Arc<RwLock<Game>>, mut socket: WebSocket, addr: SocketAddr) {
+async fn buzzer_handler(
+    state: Arc<RwLock<Quiz>>,
+    names: Arc<Names>,
+    mut socket: WebSocket,
+    addr: SocketAddr,
+    token: String,
+) {
     let mut receiver = {
-        let game = state.read().await;
-        let receiver = game.channel.subscribe();
-        let _ = game.channel.send(Default::default());
+        let quiz = state.read().await;
+        let receiver = quiz.channel.subscribe();
+        let _ = quiz.channel.send(Default::default());
         receiver
     };
-    let mut registered = None;
+    let mut latency = Latency::default();
+    let mut pings = interval(PING_INTERVAL);
     loop {
         select! {
+            _ = pings.tick() => {
+                if socket.send(Message::Ping(latency.ping())).await.is_err() {
+                    debug!("Connection closed by peer at {:?}, exiting handler", addr);
+                    break;
+                }
+            },
             update = receiver.recv() => {
-                if let Ok(update) = update {
-                    if update.teams_invalidated {
-                        registered = None;
-                    }
-                } else {
+                if let Err(RecvError::Closed) = update {
                     debug!("Internal channel closed, exiting handler");
                     break;
                 }
                 let update = {
-                    let game = state.read().await;
+                    let quiz = state.read().await;
+                    let registered = quiz.game.buzzers.get(&token).copied();
                     BuzzerUpdate {
-                        phase: game.phase.clone(),
-                        teams: game.teams.clone(),
-                        registered
+                        phase: quiz.game.phase.clone(),
+                        // Only the own team's pending name proposal is visible.
+                        teams: quiz
+                            .game
+                            .teams
+                            .iter()
+                            .map(|team| Team {
+                                proposed: team.proposed.clone().filter(|_| Some(team.id) == registered),
+                                ..team.clone()
+                            })
+                            .collect(),
+                        registered,
+                        player: quiz.game.players.get(&token).cloned(),
+                        time_left: quiz.time_left().map(|time| time.as_millis() as u64),
+                        listening_time: quiz.game.listening_time.map(|time| time.as_millis() as u64),
+                        listening_left: quiz.listening_left().map(|time| time.as_millis() as u64),
                     }
                 };
                 if let Err(_) = socket.send(Message::text(serde_json::to_string(&update).unwrap())).await {
@@ -64,29 +159,53 @@ async fn buzzer_handler(state: Arc<RwLock<Game>>, mut socket: WebSocket, addr: S
                 };
             },
             raw = socket.recv() => {
-                if let Some(Ok(Message::Text(message))) = raw {
+                let arrival = Instant::now();
+                if let Some(Ok(Message::Pong(payload))) = raw {
+                    latency.pong(&payload);
+                } else if let Some(Ok(Message::Ping(_))) = raw {
+                    // Pings are answered by axum itself.
+                } else if let Some(Ok(Message::Text(message))) = raw {
                     if let Ok(interaction) = serde_json::from_str::<BuzzerInteraction>(&message) {
+                        let mut quiz = state.write().await;
                         match interaction {
-                            BuzzerInteraction::Register { team } => {
-                                let game = state.read().await;
-                                if team < game.teams.len() {
-                                    info!("Buzzer at {:?} registered for team {:?}", addr, state.read().await.teams[team]);
-                                    registered = Some(team);
-                                    let _ = game.channel.send(Default::default());
+                            BuzzerInteraction::Register { team, player } => {
+                                let event = Event::Register { token: token.clone(), team, player };
+                                match quiz.dispatch(event).await {
+                                    Ok(()) => info!("Buzzer at {:?} registered for team {:?}", addr, quiz.game.team(team).ok()),
+                                    Err(e) => debug!("Ignoring registration of buzzer at {:?}: {e}", addr),
+                                }
+                            },
+                            BuzzerInteraction::CreateTeam { name } => {
+                                let event = Event::ProposeTeam {
+                                    token: token.clone(),
+                                    name,
+                                    placeholder: new_name(&names, &quiz),
+                                };
+                                match quiz.dispatch(event).await {
+                                    Ok(()) => info!("Buzzer at {:?} created a team", addr),
+                                    Err(e) => debug!("Ignoring team created by buzzer at {:?}: {e}", addr),
+                                }
+                            },
+                            BuzzerInteraction::ProposeName { name } => {
+                                if let Some(team) = quiz.game.buzzers.get(&token).copied() {
+                                    let event = Event::ProposeName { team, name };
+                                    if let Err(e) = quiz.dispatch(event).await {
+                                        debug!("Ignoring name proposed by buzzer at {:?}: {e}", addr);
+                                    }
                                 }
                             },
                             BuzzerInteraction::Buzz => {
-                                if let Some(team) = registered {
-                                    let mut game = state.write().await;
-                                    if let Phase::Listening { active } = &game.phase {
-                                        if active[team] {
-                                            debug!("Received valid buzz from team {} ({:?})", team, game.teams[team]);
-                                            game.phase = Phase::Guessing { active: active.clone(), team: team };
-                                            let _ = game.channel.send(Default::default());
-                                            if let Err(e) = game.spotify.pause_playback(None).await {
-                                                debug!("Spotify returned error {e}, likely not authorized");
-                                            };
-                                        }
+                                if let Some(team) = quiz.game.buzzers.get(&token).copied() {
+                                    let event = Event::Buzz {
+                                        team,
+                                        player: quiz.game.players.get(&token).cloned(),
+                                        played: quiz.stopwatch.elapsed_at(arrival),
+                                        received: quiz.timestamp(arrival),
+                                        latency: latency.estimate(),
+                                    };
+                                    match quiz.dispatch(event).await {
+                                        Ok(()) => debug!("Received valid buzz from team {} ({:?})", team, quiz.game.team(team).ok()),
+                                        Err(e) => debug!("Ignoring buzz from team {}: {e}", team),
                                     }
                                 }
                             }
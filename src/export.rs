@@ -0,0 +1,261 @@
+use std::{fmt::Write, sync::Arc};
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use axum_extra::{headers::Cookie, TypedHeader};
+use serde::{Deserialize, Serialize};
+use types::game::{PlayedSong, Round, Team};
+
+use crate::{auth::Auth, game::Game, room::Rooms};
+
+/// The results of a room, as exported after a quiz night.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Results {
+    /// The join code of the room.
+    pub room: String,
+    /// Every team with its final points.
+    pub teams: Vec<Team>,
+    /// The rounds of the match, with the points of each team.
+    pub rounds: Vec<Round>,
+    /// Every song played, with its buzzes and guesses.
+    pub songs: Vec<PlayedSong>,
+}
+
+impl Results {
+    fn new(room: String, game: &Game) -> Self {
+        Self {
+            room,
+            teams: game.teams.clone(),
+            rounds: game.rounds.clone(),
+            songs: game.log.clone(),
+        }
+    }
+
+    /// Render the results as CSV.
+    ///
+    /// Every row is a record of the kind given in the first
+    /// column: the final points of a team, a song nobody
+    /// guessed, a single guess or a single buzz with its buzz
+    /// round and place.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "record,team,points,round,song,artists,started,title,artist,after_ms,buzz_round,place\n",
+        );
+        for team in &self.teams {
+            row(&mut csv, &["team", &team.name, &team.points.to_string()]);
+        }
+        for song in &self.songs {
+            let round = song
+                .round
+                .and_then(|round| self.rounds.get(round))
+                .map_or("", |round| &round.name);
+            let artists = song.track.artists.join(", ");
+            let started = song.started.to_string();
+            if song.guesses.is_empty() {
+                row(
+                    &mut csv,
+                    &["song", "", "", round, &song.track.name, &artists, &started],
+                );
+            }
+            for guess in &song.guesses {
+                row(
+                    &mut csv,
+                    &[
+                        "guess",
+                        &guess.team,
+                        &guess.points.to_string(),
+                        round,
+                        &song.track.name,
+                        &artists,
+                        &started,
+                        &guess.title.to_string(),
+                        &guess.artist.to_string(),
+                        &guess.after.to_string(),
+                    ],
+                );
+            }
+            for (n, buzzes) in song.buzzes.iter().enumerate() {
+                for (place, team) in buzzes.iter().enumerate() {
+                    row(
+                        &mut csv,
+                        &[
+                            "buzz",
+                            team,
+                            "",
+                            round,
+                            &song.track.name,
+                            &artists,
+                            &started,
+                            "",
+                            "",
+                            "",
+                            &(n + 1).to_string(),
+                            &(place + 1).to_string(),
+                        ],
+                    );
+                }
+            }
+        }
+        csv
+    }
+}
+
+/// Append a row of the given fields to CSV, leaving
+/// the remaining columns empty.
+///
+/// Names are chosen by players, so text which spreadsheets
+/// would run as a formula is prefixed with a `'`.
+fn row(csv: &mut String, fields: &[&str]) {
+    const COLUMNS: usize = 12;
+    for n in 0..COLUMNS {
+        if n > 0 {
+            csv.push(',');
+        }
+        let field = fields.get(n).copied().unwrap_or("");
+        let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r'])
+            && field.parse::<i64>().is_err()
+        {
+            format!("'{field}")
+        } else {
+            field.to_string()
+        };
+        if field.contains([',', '"', '\n', '\r']) {
+            let _ = write!(csv, "\"{}\"", field.replace('"', "\"\""));
+        } else {
+            csv.push_str(&field);
+        }
+    }
+    csv.push('\n');
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    room: String,
+}
+
+/// Look up the results of the room in the query.
+///
+/// Only a logged in admin may export results.
+async fn results(
+    rooms: &Rooms,
+    auth: &Auth,
+    cookie: &Option<TypedHeader<Cookie>>,
+    room: String,
+) -> Result<Results, StatusCode> {
+    if !auth.verify(cookie).await {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    let quiz = rooms.get(&room).await.ok_or(StatusCode::NOT_FOUND)?;
+    let quiz = quiz.read().await;
+    Ok(Results::new(room.to_uppercase(), &quiz.game))
+}
+
+/// Export the results of a room as JSON.
+pub async fn results_json(
+    State(rooms): State<Arc<Rooms>>,
+    State(auth): State<Arc<Auth>>,
+    cookie: Option<TypedHeader<Cookie>>,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    match results(&rooms, &auth, &cookie, query.room).await {
+        Ok(results) => (
+            [(
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"results-{}.json\"", results.room),
+            )],
+            axum::Json(results),
+        )
+            .into_response(),
+        Err(status) => status.into_response(),
+    }
+}
+
+/// Export the results of a room as CSV.
+pub async fn results_csv(
+    State(rooms): State<Arc<Rooms>>,
+    State(auth): State<Arc<Auth>>,
+    cookie: Option<TypedHeader<Cookie>>,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    match results(&rooms, &auth, &cookie, query.room).await {
+        Ok(results) => (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"results-{}.csv\"", results.room),
+                ),
+            ],
+            results.to_csv(),
+        )
+            .into_response(),
+        Err(status) => status.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::{game::Guess, track::Track};
+
+    use super::*;
+
+    #[test]
+    fn csv_has_a_row_per_team_and_guess() {
+        let results = Results {
+            room: "ABCD".to_string(),
+            teams: vec![Team {
+                id: 0,
+                name: "Coole Otter".to_string(),
+                points: 3,
+                players: Vec::new(),
+                proposed: None,
+            }],
+            rounds: Vec::new(),
+            songs: vec![PlayedSong {
+                track: Track {
+                    name: "Money".to_string(),
+                    artists: vec!["Pink Floyd".to_string(), "Gast, \"live\"".to_string()],
+                    image: String::new(),
+                },
+                round: None,
+                started: 1700000000,
+                buzzes: vec![vec!["Coole Otter".to_string()]],
+                guesses: vec![Guess {
+                    team: "Coole Otter".to_string(),
+                    player: None,
+                    title: true,
+                    artist: false,
+                    points: 1,
+                    after: 4200,
+                }],
+            }],
+        };
+        let csv = results.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "team,Coole Otter,3,,,,,,,,,");
+        assert_eq!(
+            lines[2],
+            "guess,Coole Otter,1,,Money,\"Pink Floyd, Gast, \"\"live\"\"\",1700000000,true,false,4200,,"
+        );
+        assert_eq!(
+            lines[3],
+            "buzz,Coole Otter,,,Money,\"Pink Floyd, Gast, \"\"live\"\"\",1700000000,,,,1,1"
+        );
+    }
+
+    #[test]
+    fn csv_does_not_run_formulas() {
+        let mut csv = String::new();
+        row(&mut csv, &["team", "=HYPERLINK(\"x\")", "-3"]);
+        row(&mut csv, &["team", "@SUM(A1)", "+1"]);
+        row(&mut csv, &["team", "-Otter", "0"]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "team,\"'=HYPERLINK(\"\"x\"\")\",-3,,,,,,,,,");
+        assert_eq!(lines[1], "team,'@SUM(A1),+1,,,,,,,,,");
+        assert_eq!(lines[2], "team,'-Otter,0,,,,,,,,,");
+    }
+}
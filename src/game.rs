@@ -1,32 +1,2385 @@
-use rspotify::{model::FullTrack, AuthCodeSpotify};
-use tokio::sync::broadcast::Sender;
-use types::game::{Phase, Team};
+use std::{collections::HashMap, fmt::Display, time::Duration};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use types::{
+    game::{Buzz, Guess, Phase, PlayedSong, Player, Round, Solved, Team, TeamId},
+    scoring::ScoringRules,
+    track::{LibraryEntry, Track},
+};
+
+use crate::names::{check_name, same_name, NameProblem};
 
 /// An internal update.
 ///
 /// This is sent to every handler on an internal state change
 /// in a Game.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Update {
-    pub teams_invalidated: bool,
+    /// The teams which were removed, whose buzzers are no
+    /// longer registered.
+    pub removed: Vec<TeamId>,
+}
+
+/// Something that happens to a game.
+///
+/// Events are produced by the websocket handlers from the
+/// interactions of admins and buzzers and fed into
+/// [`Game::apply`]. Anything requiring I/O, such as looking
+/// up a track, has already happened by the time an event
+/// is created.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The admin selected the track with the given id, to be
+    /// played from the given position, at the given time in
+    /// seconds since the Unix epoch.
+    ///
+    /// If a snippet length is given, the song is paused once
+    /// it has played for that long.
+    Select {
+        id: String,
+        track: Track,
+        start: Duration,
+        snippet: Option<Duration>,
+        at: u64,
+    },
+    /// The team with the given ID pressed its buzzer.
+    ///
+    /// `played` is how long the song had been playing, `received`
+    /// when the buzz arrived on the server's clock and `latency`
+    /// the estimated one-way latency of the buzzer. `player` is
+    /// the nickname the buzzer is registered with, if any.
+    Buzz {
+        team: TeamId,
+        player: Option<String>,
+        played: Duration,
+        received: Duration,
+        latency: Duration,
+    },
+    /// The admin stopped the listening phase.
+    StopListening,
+    /// The admin accepted the given parts of the guess
+    /// of the guessing team.
+    Accept { title: bool, artist: bool },
+    /// The admin rejected the guess of the guessing team.
+    RejectGuess,
+    /// The admin created a team with the given name.
+    CreateTeam { name: String },
+    /// The admin renamed the team with the given ID.
+    RenameTeam { team: TeamId, name: String },
+    /// The admin deleted the team with the given ID.
+    DeleteTeam { team: TeamId },
+    /// The admin chose the name pack new team names are
+    /// generated from, or the default one.
+    SetNamePack { pack: Option<String> },
+    /// The buzzer with the given token created a team with the
+    /// given name and registered for it.
+    ///
+    /// While names need approval, the team goes by the given
+    /// placeholder until its name is approved.
+    ProposeTeam {
+        token: String,
+        name: String,
+        placeholder: String,
+    },
+    /// A buzzer proposed a new name for the team with the given ID.
+    ProposeName { team: TeamId, name: String },
+    /// The admin approved the name proposed for the team with
+    /// the given ID.
+    ApproveName { team: TeamId },
+    /// The admin rejected the name proposed for the team with
+    /// the given ID.
+    RejectName { team: TeamId },
+    /// The admin enabled or disabled approving names proposed
+    /// by buzzers.
+    SetNameApproval { enabled: bool },
+    /// The admin appended the given tracks to the queue.
+    Enqueue { entries: Vec<LibraryEntry> },
+    /// The admin moved a queue entry to another position.
+    MoveQueueEntry { from: usize, to: usize },
+    /// The admin removed the queue entry at the given index.
+    RemoveQueueEntry { index: usize },
+    /// The admin started the first track of the queue at the
+    /// given time in seconds since the Unix epoch.
+    PlayNext { at: u64 },
+    /// The admin changed the scoring rules.
+    SetScoring { rules: ScoringRules },
+    /// The admin added the given amount of points to the team
+    /// with the given ID.
+    AdjustPoints { team: TeamId, delta: i64 },
+    /// The admin set the points of the team with the given ID.
+    SetPoints { team: TeamId, points: i64 },
+    /// The admin reverted the last applied event.
+    Undo,
+    /// The admin enabled or disabled queueing buzzes.
+    SetBuzzQueue { enabled: bool },
+    /// The admin changed how long teams may take to answer.
+    SetAnswerTime { time: Option<Duration> },
+    /// The answer timer of the given guessing turn ran out.
+    TimeUp { turn: u64 },
+    /// The admin changed how long a song may be listened to.
+    SetListeningTime { time: Option<Duration> },
+    /// The listening limit of the given listening turn ran out.
+    ListeningTimeUp { turn: u64 },
+    /// The snippet of the song ended during the given
+    /// listening turn.
+    SnippetOver { turn: u64 },
+    /// The buzzer with the given token registered for the
+    /// team with the given ID, optionally as the player with
+    /// the given nickname.
+    Register {
+        token: String,
+        team: TeamId,
+        player: Option<String>,
+    },
+    /// The admin started a new match with the given rounds.
+    ///
+    /// Without any rounds, songs are played freely.
+    StartMatch { rounds: Vec<Round> },
+    /// The admin ended the current round, or started the
+    /// next one after the results were shown.
+    NextRound,
+    /// The admin finished the match.
+    FinishMatch,
+    /// The admin left the finished match to play songs freely.
+    LeaveMatch,
+}
+
+/// A side effect of applying an event.
+///
+/// These are carried out in order by the caller of [`Game::apply`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    /// Start playing the track with the given id from the
+    /// given position.
+    Play { id: String, position: Duration },
+    /// Pause playback.
+    Pause,
+    /// Resume playback.
+    Resume,
+    /// Notify every handler of the changed game.
+    Broadcast(Update),
+    /// Start the answer timer of the given guessing turn,
+    /// which feeds [`Event::TimeUp`] back after the given time.
+    StartTimer { turn: u64, time: Duration },
+    /// Limit the given listening turn to the given total playing
+    /// time of the song, after which [`Event::ListeningTimeUp`]
+    /// is fed back.
+    LimitListening { turn: u64, time: Duration },
+    /// End the snippet of the song once it has played for the
+    /// given total time by feeding back [`Event::SnippetOver`].
+    EndSnippet { turn: u64, time: Duration },
+}
+
+/// The reason an event could not be applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    /// The event is not allowed in the current phase.
+    WrongPhase,
+    /// There is no team with the given ID.
+    UnknownTeam(TeamId),
+    /// The team with the given ID may not buzz right now.
+    Inactive(TeamId),
+    /// There is no queue entry at the given index.
+    UnknownQueueEntry(usize),
+    /// The accepted parts of the song were already solved.
+    AlreadySolved,
+    /// There is no event left which could be undone.
+    NothingToUndo,
+    /// The timer belongs to a turn which is over.
+    StaleTimer,
+    /// All songs of the current round have been played.
+    RoundComplete,
+    /// There is no further round.
+    NoRoundLeft,
+    /// Another team already has the given name.
+    NameTaken(String),
+    /// The proposed name is not acceptable.
+    InvalidName(NameProblem),
+    /// No name was proposed for the team with the given ID.
+    NoProposal(TeamId),
+    /// There are already [`MAX_TEAMS`] teams.
+    TooManyTeams,
+    /// The buzzer already created the team with the given ID.
+    AlreadyCreated(TeamId),
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WrongPhase => write!(f, "event not allowed in the current phase"),
+            Self::UnknownTeam(team) => write!(f, "no team with ID {team}"),
+            Self::Inactive(team) => write!(f, "team with ID {team} is not active"),
+            Self::UnknownQueueEntry(index) => write!(f, "no queue entry at index {index}"),
+            Self::AlreadySolved => write!(f, "accepted parts were already solved"),
+            Self::NothingToUndo => write!(f, "no event left to undo"),
+            Self::StaleTimer => write!(f, "timer of a finished turn"),
+            Self::RoundComplete => write!(f, "all songs of the round have been played"),
+            Self::NoRoundLeft => write!(f, "no further round"),
+            Self::NameTaken(name) => write!(f, "name {name:?} is already taken"),
+            Self::InvalidName(problem) => write!(f, "invalid name: {problem}"),
+            Self::NoProposal(team) => write!(f, "no name proposed for team with ID {team}"),
+            Self::TooManyTeams => write!(f, "there are already {MAX_TEAMS} teams"),
+            Self::AlreadyCreated(team) => write!(f, "buzzer already created team with ID {team}"),
+        }
+    }
 }
 
+impl std::error::Error for GameError {}
+
 /// The server-side representation of a game.
 ///
-/// This includes pure game state as well as communication
-/// channels to update websocket handlers and Spotify API.
-/// handlers.
-#[derive(Debug, Clone)]
+/// This is the pure game state. All rules live in
+/// [`Game::apply`], which never performs any I/O itself.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Game {
     /// Which phase the game is currently in.
     pub phase: Phase,
     /// Which teams currently exist in-game.
     pub teams: Vec<Team>,
+    /// The ID the next created team gets.
+    pub next_team: TeamId,
     /// Which song is currently selected.
-    pub song: Option<FullTrack>,
-    /// The channel which is used to communicate a change
-    /// of the game state.
-    pub channel: Sender<Update>,
-    /// The Spotify API connection used to play songs.
-    pub spotify: AuthCodeSpotify,
+    pub song: Option<Track>,
+    /// Which songs are up next.
+    pub queue: Vec<LibraryEntry>,
+    /// How guesses are scored.
+    pub rules: ScoringRules,
+    /// How long the song had been playing when the
+    /// guessing team buzzed.
+    pub buzzed_after: Duration,
+    /// Which team each buzzer is registered for, by the
+    /// persistent token of the buzzer.
+    pub buzzers: HashMap<String, TeamId>,
+    /// Which team each buzzer created, by the persistent
+    /// token of the buzzer.
+    pub creators: HashMap<String, TeamId>,
+    /// Which player each buzzer is registered as, by the
+    /// persistent token of the buzzer.
+    pub players: HashMap<String, String>,
+    /// Whether teams buzzing while another team is guessing
+    /// are queued up to guess next.
+    pub queue_buzzes: bool,
+    /// How long a team may take to answer before its guess
+    /// is rejected, if there is a limit.
+    pub answer_time: Option<Duration>,
+    /// How long a song may play before it is revealed,
+    /// if there is a limit.
+    pub listening_time: Option<Duration>,
+    /// How long the current song plays before it is paused,
+    /// if only a snippet of it is played.
+    pub snippet: Option<Duration>,
+    /// Whether the snippet of the current song has ended.
+    pub snippet_over: bool,
+    /// Whether the current song is playing, as left by the
+    /// playback effects applied so far.
+    pub playing: bool,
+    /// The rounds of the current match, if it is played in rounds.
+    pub rounds: Vec<Round>,
+    /// The index of the current round.
+    pub round: usize,
+    /// How many songs of the current round have been played.
+    pub played: usize,
+    /// Every song played so far and what happened during it.
+    pub log: Vec<PlayedSong>,
+    /// How often the log changed, which tells handlers when
+    /// to send it again.
+    #[serde(skip)]
+    pub(crate) log_version: u64,
+    /// The name pack new team names are generated from,
+    /// if not the default one.
+    pub name_pack: Option<String>,
+    /// Whether names proposed by buzzers need the approval
+    /// of an admin before they are shown.
+    pub approve_names: bool,
+    /// The number of the current guessing or listening turn,
+    /// which tells timers of earlier turns apart.
+    pub turn: u64,
+    /// How long after the first buzz of a round other buzzes
+    /// may still win by compensated time.
+    pub tie_window: Duration,
+    /// The buzzes of the current buzz round.
+    ///
+    /// These refer to the server's clock at the time and are
+    /// therefore not persisted.
+    #[serde(skip)]
+    pub(crate) buzzes: Vec<BuzzRecord>,
+    /// The states before the most recently applied events.
+    ///
+    /// Selecting a new song clears this, as its playback
+    /// cannot be reverted.
+    #[serde(skip)]
+    pub(crate) history: Vec<Game>,
+}
+
+/// A buzz as received by the server.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BuzzRecord {
+    team: TeamId,
+    player: Option<String>,
+    played: Duration,
+    received: Duration,
+    latency: Duration,
+}
+
+impl BuzzRecord {
+    /// When the buzzer was pressed, as far as can be told.
+    ///
+    /// The latency is capped by the tie window, so a buzzer
+    /// cannot gain more by pretending to be slow.
+    fn pressed(&self, window: Duration) -> Duration {
+        self.received.saturating_sub(self.latency.min(window))
+    }
+
+    /// How long the song had been playing when the buzzer was pressed.
+    fn played(&self, window: Duration) -> Duration {
+        self.played.saturating_sub(self.latency.min(window))
+    }
+}
+
+/// The player of the given team with the given nickname,
+/// who is added if the team has none yet.
+fn player_mut<'a>(team: &'a mut Team, name: &str) -> &'a mut Player {
+    let index = match team.players.iter().position(|player| player.name == name) {
+        Some(index) => index,
+        None => {
+            team.players.push(Player {
+                name: name.to_string(),
+                ..Default::default()
+            });
+            team.players.len() - 1
+        }
+    };
+    &mut team.players[index]
+}
+
+/// How many events can be undone at most.
+const UNDO_DEPTH: usize = 20;
+
+/// How many teams a game may have at most.
+pub const MAX_TEAMS: usize = 50;
+
+impl Game {
+    /// Apply an event to the game.
+    ///
+    /// On success the game has transitioned to its new state and
+    /// the returned effects have to be carried out by the caller.
+    /// On failure the game is left unchanged.
+    pub fn apply(&mut self, event: Event) -> Result<Vec<Effect>, GameError> {
+        let effects = self.apply_event(event)?;
+        for effect in &effects {
+            match effect {
+                Effect::Play { .. } | Effect::Resume => self.playing = true,
+                Effect::Pause => self.playing = false,
+                _ => (),
+            }
+        }
+        Ok(effects)
+    }
+
+    fn apply_event(&mut self, event: Event) -> Result<Vec<Effect>, GameError> {
+        match event {
+            Event::Undo => self.undo(),
+            // A registration is not a move in the game, so it can
+            // neither be undone nor does it discard the history.
+            Event::Register { .. } => self.transition(event),
+            Event::ProposeTeam { .. } | Event::ProposeName { .. } => self.apply_buzzer(event),
+            Event::Select { .. } | Event::PlayNext { .. } => {
+                let effects = self.transition(event)?;
+                self.history.clear();
+                Ok(effects)
+            }
+            event => {
+                let mut history = std::mem::take(&mut self.history);
+                let before = self.clone();
+                let result = self.transition(event);
+                if result.is_ok() {
+                    history.push(before);
+                    if history.len() > UNDO_DEPTH {
+                        history.remove(0);
+                    }
+                }
+                self.history = history;
+                result
+            }
+        }
+    }
+
+    /// Apply an event originating from a buzzer.
+    ///
+    /// Like registrations, these are no moves of the admin and
+    /// cannot be undone. Instead, the changed teams are carried
+    /// over to the earlier states, so undoing a move of the
+    /// admin keeps them.
+    fn apply_buzzer(&mut self, event: Event) -> Result<Vec<Effect>, GameError> {
+        let before = self.teams.clone();
+        let effects = self.transition(event)?;
+        for team in &self.teams {
+            let former = before.iter().find(|former| former.id == team.id);
+            for state in &mut self.history {
+                match (former, state.team_mut(team.id)) {
+                    (None, Err(_)) => state.teams.push(team.clone()),
+                    (Some(former), Ok(entry)) => {
+                        if former.name != team.name {
+                            entry.name = team.name.clone();
+                        }
+                        if former.proposed != team.proposed {
+                            entry.proposed = team.proposed.clone();
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+        Ok(effects)
+    }
+
+    fn transition(&mut self, event: Event) -> Result<Vec<Effect>, GameError> {
+        match event {
+            Event::Select {
+                id,
+                track,
+                start,
+                snippet,
+                at,
+            } => self.select(id, track, start, snippet, at),
+            Event::Buzz {
+                team,
+                player,
+                played,
+                received,
+                latency,
+            } => {
+                let record = BuzzRecord {
+                    team,
+                    player,
+                    played,
+                    received,
+                    latency,
+                };
+                match &self.phase {
+                    Phase::Listening { active, solved } => {
+                        if !active.contains(&team) {
+                            return Err(GameError::Inactive(team));
+                        }
+                        self.phase = Phase::Guessing {
+                            active: active.clone(),
+                            team,
+                            solved: *solved,
+                            queue: Vec::new(),
+                        };
+                        self.buzzed_after = record.played(self.tie_window);
+                        self.count_buzz(&record);
+                        self.buzzes = vec![record];
+                        self.log_buzzes(true);
+                        let mut effects = vec![Effect::Pause];
+                        effects.extend(self.start_turn());
+                        effects.push(Effect::Broadcast(Default::default()));
+                        Ok(effects)
+                    }
+                    Phase::Guessing {
+                        active,
+                        team: guessing,
+                        solved,
+                        queue,
+                    } => {
+                        // The window starts with the first buzz to arrive,
+                        // no matter which buzz wins after compensation.
+                        let within = self
+                            .buzzes
+                            .iter()
+                            .map(|buzz| buzz.received)
+                            .min()
+                            .is_some_and(|first| received <= first + self.tie_window);
+                        if !within && !self.queue_buzzes {
+                            return Err(GameError::WrongPhase);
+                        }
+                        if !active.contains(&team)
+                            || team == *guessing
+                            || self.buzzes.iter().any(|buzz| buzz.team == team)
+                        {
+                            return Err(GameError::Inactive(team));
+                        }
+                        let (active, solved) = (active.clone(), *solved);
+                        let (guessing, mut queue) = (*guessing, queue.clone());
+                        self.count_buzz(&record);
+                        self.buzzes.push(record);
+                        let window = self.tie_window;
+                        self.buzzes
+                            .sort_by_key(|buzz| (buzz.pressed(window), buzz.received));
+                        let guessing = if within {
+                            let winner = &self.buzzes[0];
+                            self.buzzed_after = winner.played(window);
+                            queue = self.buzzes[1..].iter().map(|buzz| buzz.team).collect();
+                            winner.team
+                        } else {
+                            queue.push(team);
+                            guessing
+                        };
+                        if !self.queue_buzzes {
+                            queue.clear();
+                        }
+                        self.log_buzzes(false);
+                        let mut effects = Vec::new();
+                        if let Phase::Guessing { team, .. } = self.phase {
+                            if team != guessing {
+                                effects.extend(self.start_turn());
+                            }
+                        }
+                        self.phase = Phase::Guessing {
+                            active,
+                            team: guessing,
+                            solved,
+                            queue,
+                        };
+                        effects.push(Effect::Broadcast(Default::default()));
+                        Ok(effects)
+                    }
+                    Phase::Selection | Phase::RoundOver { .. } | Phase::Finished => {
+                        Err(GameError::WrongPhase)
+                    }
+                }
+            }
+            Event::StopListening => {
+                let Phase::Listening { .. } = self.phase else {
+                    return Err(GameError::WrongPhase);
+                };
+                self.phase = Phase::Selection;
+                Ok(vec![Effect::Resume, Effect::Broadcast(Default::default())])
+            }
+            Event::Accept { title, artist } => {
+                let Phase::Guessing {
+                    team,
+                    active,
+                    solved,
+                    ..
+                } = self.phase.clone()
+                else {
+                    return Err(GameError::WrongPhase);
+                };
+                let title = title && !solved.title;
+                let artist = artist && !solved.artist;
+                if !title && !artist {
+                    return Err(GameError::AlreadySolved);
+                }
+                let points = self.rules.award(title, artist, self.buzzed_after);
+                let player = self.guessing_player(team);
+                let entry = self.team_mut(team)?;
+                entry.points += points;
+                if let Some(player) = player {
+                    player_mut(entry, &player).correct += 1;
+                }
+                self.log_guess(team, title, artist, points);
+                let solved = Solved {
+                    title: solved.title || title,
+                    artist: solved.artist || artist,
+                };
+                let limits = if solved.complete() {
+                    self.phase = Phase::Selection;
+                    Vec::new()
+                } else {
+                    self.continue_without(team, &active, solved)
+                };
+                let mut effects: Vec<Effect> = self.resume().into_iter().collect();
+                effects.extend(limits);
+                effects.push(Effect::Broadcast(Default::default()));
+                Ok(effects)
+            }
+            Event::RejectGuess => self.reject(),
+            Event::TimeUp { turn } => {
+                let Phase::Guessing { .. } = self.phase else {
+                    return Err(GameError::StaleTimer);
+                };
+                if turn != self.turn || self.answer_time.is_none() {
+                    return Err(GameError::StaleTimer);
+                }
+                info!("Answer time is up");
+                self.reject()
+            }
+            Event::ListeningTimeUp { turn } => {
+                let Phase::Listening { .. } = self.phase else {
+                    return Err(GameError::StaleTimer);
+                };
+                if turn != self.turn || self.listening_time.is_none() {
+                    return Err(GameError::StaleTimer);
+                }
+                info!("Listening time is up");
+                self.phase = Phase::Selection;
+                Ok(vec![Effect::Pause, Effect::Broadcast(Default::default())])
+            }
+            Event::SnippetOver { turn } => {
+                let Phase::Listening { .. } = self.phase else {
+                    return Err(GameError::StaleTimer);
+                };
+                if turn != self.turn {
+                    return Err(GameError::StaleTimer);
+                }
+                info!("Snippet is over");
+                self.snippet_over = true;
+                // Nothing plays anymore, so the listening limit of the
+                // turn is dropped.
+                let mut effects = vec![Effect::Pause];
+                effects.extend(self.start_listening());
+                effects.push(Effect::Broadcast(Default::default()));
+                Ok(effects)
+            }
+            Event::SetListeningTime { time } => {
+                info!("Listening time changed: {:?}", time);
+                self.listening_time = time;
+                let mut effects = Vec::new();
+                // The listening turn starts over with the new limit,
+                // which also invalidates the pending one.
+                if let Phase::Listening { .. } = self.phase {
+                    effects.extend(self.start_listening());
+                }
+                effects.push(Effect::Broadcast(Default::default()));
+                Ok(effects)
+            }
+            Event::SetAnswerTime { time } => {
+                info!("Answer time changed: {:?}", time);
+                self.answer_time = time;
+                let mut effects = Vec::new();
+                // The guessing team gets a fresh timer with the new
+                // limit, which also invalidates the running one.
+                if let Phase::Guessing { .. } = self.phase {
+                    effects.extend(self.start_turn());
+                }
+                effects.push(Effect::Broadcast(Default::default()));
+                Ok(effects)
+            }
+            Event::CreateTeam { name } => {
+                if self.teams.len() >= MAX_TEAMS {
+                    return Err(GameError::TooManyTeams);
+                }
+                self.check_unique(&name, None)?;
+                let team = Team {
+                    id: self.next_team,
+                    name,
+                    points: 0,
+                    players: Vec::new(),
+                    proposed: None,
+                };
+                info!("Team created: {:?}", &team);
+                self.next_team += 1;
+                self.teams.push(team);
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::RenameTeam { team, name } => {
+                self.check_unique(&name, Some(team))?;
+                let entry = self.team_mut(team)?;
+                let former = entry.clone();
+                entry.name = name;
+                info!("Team renamed: former={:?} current={:?}", former, entry);
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::DeleteTeam { team } => {
+                let index = self
+                    .teams
+                    .iter()
+                    .position(|entry| entry.id == team)
+                    .ok_or(GameError::UnknownTeam(team))?;
+                let removed = self.teams.remove(index);
+                info!("Team removed: {:?}", removed);
+                for round in &mut self.rounds {
+                    round.points.remove(&team);
+                }
+                // Only the buzzers of the removed team have to
+                // register again.
+                self.buzzers.retain(|_, registered| *registered != team);
+                self.creators.retain(|_, created| *created != team);
+                let buzzers = &self.buzzers;
+                self.players.retain(|token, _| buzzers.contains_key(token));
+                self.buzzes.retain(|buzz| buzz.team != team);
+                let mut effects = self.withdraw(team);
+                effects.push(Effect::Broadcast(Update {
+                    removed: vec![team],
+                }));
+                Ok(effects)
+            }
+            Event::Enqueue { entries } => {
+                self.queue.extend(entries);
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::MoveQueueEntry { from, to } => {
+                if from >= self.queue.len() {
+                    return Err(GameError::UnknownQueueEntry(from));
+                }
+                if to >= self.queue.len() {
+                    return Err(GameError::UnknownQueueEntry(to));
+                }
+                let entry = self.queue.remove(from);
+                self.queue.insert(to, entry);
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::RemoveQueueEntry { index } => {
+                if index >= self.queue.len() {
+                    return Err(GameError::UnknownQueueEntry(index));
+                }
+                self.queue.remove(index);
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::PlayNext { at } => {
+                let Phase::Selection = self.phase else {
+                    return Err(GameError::WrongPhase);
+                };
+                if self.queue.is_empty() {
+                    return Err(GameError::UnknownQueueEntry(0));
+                }
+                let entry = self.queue[0].clone();
+                let effects = self.select(entry.id, entry.track, Duration::ZERO, None, at)?;
+                self.queue.remove(0);
+                Ok(effects)
+            }
+            Event::SetScoring { rules } => {
+                info!("Scoring rules changed: {:?}", rules);
+                self.rules = rules;
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::ProposeTeam {
+                token,
+                name,
+                placeholder,
+            } => {
+                let Phase::Selection = self.phase else {
+                    return Err(GameError::WrongPhase);
+                };
+                if let Some(team) = self.creators.get(&token) {
+                    return Err(GameError::AlreadyCreated(*team));
+                }
+                if self.teams.len() >= MAX_TEAMS {
+                    return Err(GameError::TooManyTeams);
+                }
+                let name = check_name(&name).map_err(GameError::InvalidName)?;
+                self.check_unique(&name, None)?;
+                let id = self.next_team;
+                let mut team = if self.approve_names {
+                    self.check_unique(&placeholder, None)?;
+                    Team {
+                        id,
+                        name: placeholder,
+                        points: 0,
+                        players: Vec::new(),
+                        proposed: Some(name),
+                    }
+                } else {
+                    Team {
+                        id,
+                        name,
+                        points: 0,
+                        players: Vec::new(),
+                        proposed: None,
+                    }
+                };
+                // The player moves on to the new team.
+                let nickname = self.players.get(&token).cloned();
+                self.release_player(&token);
+                if let Some(nickname) = nickname {
+                    player_mut(&mut team, &nickname);
+                    self.players.insert(token.clone(), nickname);
+                }
+                info!("Team created by buzzer: {:?}", &team);
+                self.next_team += 1;
+                self.teams.push(team);
+                self.creators.insert(token.clone(), id);
+                self.buzzers.insert(token, id);
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::ProposeName { team, name } => {
+                let Phase::Selection = self.phase else {
+                    return Err(GameError::WrongPhase);
+                };
+                self.team(team)?;
+                let name = check_name(&name).map_err(GameError::InvalidName)?;
+                self.check_unique(&name, Some(team))?;
+                let approve = self.approve_names;
+                let entry = self.team_mut(team)?;
+                if approve {
+                    info!("Name proposed for {:?}: {:?}", entry, name);
+                    entry.proposed = Some(name);
+                } else {
+                    info!("Team renamed by buzzer: former={:?} current={:?}", entry.name, name);
+                    entry.name = name;
+                    entry.proposed = None;
+                }
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::ApproveName { team } => {
+                let entry = self.team(team)?;
+                let name = entry.proposed.clone().ok_or(GameError::NoProposal(team))?;
+                self.check_unique(&name, Some(team))?;
+                let entry = self.team_mut(team)?;
+                info!("Name approved: former={:?} current={:?}", entry.name, name);
+                entry.name = name;
+                entry.proposed = None;
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::RejectName { team } => {
+                let entry = self.team_mut(team)?;
+                let name = entry.proposed.take().ok_or(GameError::NoProposal(team))?;
+                info!("Name rejected for {:?}: {:?}", entry.name, name);
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::SetNameApproval { enabled } => {
+                info!("Name approval enabled: {}", enabled);
+                self.approve_names = enabled;
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::SetNamePack { pack } => {
+                info!("Name pack changed: {:?}", pack);
+                self.name_pack = pack;
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::SetBuzzQueue { enabled } => {
+                info!("Buzz queue enabled: {}", enabled);
+                self.queue_buzzes = enabled;
+                if let Phase::Guessing { queue, .. } = &mut self.phase {
+                    if !enabled {
+                        queue.clear();
+                    }
+                }
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::AdjustPoints { team, delta } => {
+                let entry = self.team_mut(team)?;
+                entry.points += delta;
+                info!("Points adjusted by {}: {:?}", delta, entry);
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::SetPoints { team, points } => {
+                let entry = self.team_mut(team)?;
+                entry.points = points;
+                info!("Points set: {:?}", entry);
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::Register {
+                token,
+                team,
+                player,
+            } => {
+                let name = player
+                    .filter(|name| !name.trim().is_empty())
+                    .map(|name| check_name(&name))
+                    .transpose()
+                    .map_err(GameError::InvalidName)?;
+                self.team(team)?;
+                let released = self.release_player(&token);
+                if let Some(name) = name {
+                    let entry = self.team_mut(team)?;
+                    let known = entry.players.iter().any(|player| player.name == name);
+                    match released {
+                        // A player who only changed their nickname
+                        // keeps their statistics.
+                        Some((from, player)) if from == team && !known => {
+                            entry.players.push(Player {
+                                name: name.clone(),
+                                ..player
+                            });
+                        }
+                        _ => {
+                            player_mut(entry, &name);
+                        }
+                    }
+                    self.players.insert(token.clone(), name);
+                }
+                self.buzzers.insert(token, team);
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::StartMatch { rounds } => {
+                if let Phase::Listening { .. } | Phase::Guessing { .. } = self.phase {
+                    return Err(GameError::WrongPhase);
+                }
+                info!("Match started with rounds {:?}", rounds);
+                self.rounds = rounds
+                    .into_iter()
+                    .map(|round| Round {
+                        points: Default::default(),
+                        ..round
+                    })
+                    .collect();
+                self.round = 0;
+                self.played = 0;
+                for team in &mut self.teams {
+                    team.points = 0;
+                }
+                self.phase = Phase::Selection;
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::NextRound => match self.phase {
+                Phase::Selection => {
+                    if self.round >= self.rounds.len() {
+                        return Err(GameError::NoRoundLeft);
+                    }
+                    self.end_round();
+                    self.phase = Phase::RoundOver { round: self.round };
+                    Ok(vec![Effect::Broadcast(Default::default())])
+                }
+                Phase::RoundOver { round } => {
+                    if round + 1 >= self.rounds.len() {
+                        return Err(GameError::NoRoundLeft);
+                    }
+                    self.round = round + 1;
+                    self.played = 0;
+                    info!("Round {:?} started", self.rounds[self.round].name);
+                    self.phase = Phase::Selection;
+                    Ok(vec![Effect::Broadcast(Default::default())])
+                }
+                _ => Err(GameError::WrongPhase),
+            },
+            Event::FinishMatch => {
+                match self.phase {
+                    Phase::Selection => {
+                        if self.round < self.rounds.len() {
+                            self.end_round();
+                        }
+                    }
+                    Phase::RoundOver { .. } => (),
+                    _ => return Err(GameError::WrongPhase),
+                }
+                info!("Match finished: {:?}", self.teams);
+                self.phase = Phase::Finished;
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::LeaveMatch => {
+                let Phase::Finished = self.phase else {
+                    return Err(GameError::WrongPhase);
+                };
+                // The rounds are kept for the results, but none
+                // of them is played anymore.
+                info!("Match left");
+                self.round = self.rounds.len();
+                self.played = 0;
+                self.phase = Phase::Selection;
+                Ok(vec![Effect::Broadcast(Default::default())])
+            }
+            Event::Undo => unreachable!("undo is handled by Game::apply"),
+        }
+    }
+
+    /// Reject the guess of the guessing team.
+    ///
+    /// If buzzes are queued, the next team answers right away,
+    /// otherwise the remaining teams keep listening.
+    fn reject(&mut self) -> Result<Vec<Effect>, GameError> {
+        let Phase::Guessing {
+            team,
+            active,
+            solved,
+            queue,
+        } = self.phase.clone()
+        else {
+            return Err(GameError::WrongPhase);
+        };
+        self.team_mut(team)?.points += self.rules.wrong;
+        self.log_guess(team, false, false, self.rules.wrong);
+        let mut effects = self.pass_turn(team, active, solved, queue);
+        effects.push(Effect::Broadcast(Default::default()));
+        Ok(effects)
+    }
+
+    /// End the guessing turn of the given team.
+    ///
+    /// If buzzes are queued, the next team answers right away,
+    /// otherwise the remaining teams keep listening.
+    fn pass_turn(
+        &mut self,
+        team: TeamId,
+        mut active: Vec<TeamId>,
+        solved: Solved,
+        mut queue: Vec<TeamId>,
+    ) -> Vec<Effect> {
+        if queue.is_empty() {
+            let limits = self.continue_without(team, &active, solved);
+            let mut effects: Vec<Effect> = self.resume().into_iter().collect();
+            effects.extend(limits);
+            return effects;
+        }
+        // The next queued team answers right away, so the
+        // song stays paused.
+        let next = queue.remove(0);
+        active.retain(|active| *active != team);
+        if let Some(buzz) = self.buzzes.iter().find(|buzz| buzz.team == next) {
+            self.buzzed_after = buzz.played(self.tie_window);
+        }
+        self.phase = Phase::Guessing {
+            active,
+            team: next,
+            solved,
+            queue,
+        };
+        self.start_turn().into_iter().collect()
+    }
+
+    /// Take the given removed team out of the current song.
+    ///
+    /// If it was guessing, its turn passes on as if its guess
+    /// was rejected, but without costing any points.
+    fn withdraw(&mut self, team: TeamId) -> Vec<Effect> {
+        match self.phase.clone() {
+            Phase::Listening { mut active, solved } => {
+                active.retain(|active| *active != team);
+                self.phase = if active.is_empty() {
+                    Phase::Selection
+                } else {
+                    Phase::Listening { active, solved }
+                };
+                Vec::new()
+            }
+            Phase::Guessing {
+                mut active,
+                team: guessing,
+                solved,
+                mut queue,
+            } => {
+                queue.retain(|queued| *queued != team);
+                if guessing == team {
+                    return self.pass_turn(team, active, solved, queue);
+                }
+                active.retain(|active| *active != team);
+                self.phase = Phase::Guessing {
+                    active,
+                    team: guessing,
+                    solved,
+                    queue,
+                };
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The team with the given ID.
+    pub fn team(&self, id: TeamId) -> Result<&Team, GameError> {
+        self.teams
+            .iter()
+            .find(|team| team.id == id)
+            .ok_or(GameError::UnknownTeam(id))
+    }
+
+    /// The team with the given ID, to be modified.
+    fn team_mut(&mut self, id: TeamId) -> Result<&mut Team, GameError> {
+        self.teams
+            .iter_mut()
+            .find(|team| team.id == id)
+            .ok_or(GameError::UnknownTeam(id))
+    }
+
+    /// Bring a game restored from a snapshot into a state
+    /// that can be continued.
+    ///
+    /// Playback does not survive a restart, so a song which was
+    /// being listened to is over. A team which was guessing may
+    /// still answer, as the song was paused anyway.
+    pub fn recover(&mut self) {
+        self.assign_team_ids();
+        self.playing = false;
+        if let Phase::Listening { .. } = self.phase {
+            info!("Song interrupted by restart");
+            self.phase = Phase::Selection;
+        }
+    }
+
+    /// Give every team a distinct ID, if they do not have one.
+    ///
+    /// Snapshots from before teams had IDs are numbered by the
+    /// position of each team, which buzzers were registered by.
+    pub fn assign_team_ids(&mut self) {
+        let mut ids: Vec<TeamId> = self.teams.iter().map(|team| team.id).collect();
+        ids.sort();
+        ids.dedup();
+        if ids.len() < self.teams.len() {
+            for (n, team) in self.teams.iter_mut().enumerate() {
+                team.id = n as TeamId;
+            }
+        }
+        let next = self.teams.iter().map(|team| team.id + 1).max();
+        self.next_team = self.next_team.max(next.unwrap_or(0));
+    }
+
+    /// Start the current turn over with fresh timers, e.g. after
+    /// the game was restored and its timers were lost.
+    pub fn restart_timers(&mut self) -> Vec<Effect> {
+        match self.phase {
+            Phase::Guessing { .. } => self.start_turn().into_iter().collect(),
+            Phase::Listening { .. } => self.start_listening(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Start a new guessing turn and its answer timer, if any.
+    fn start_turn(&mut self) -> Option<Effect> {
+        self.turn += 1;
+        self.answer_time.map(|time| Effect::StartTimer {
+            turn: self.turn,
+            time,
+        })
+    }
+
+    /// Record the current buzz round of the current song in the log,
+    /// as a new buzz round if asked to.
+    fn log_buzzes(&mut self, new: bool) {
+        let names = self
+            .buzzes
+            .iter()
+            .filter_map(|buzz| self.team(buzz.team).ok())
+            .map(|team| team.name.clone())
+            .collect();
+        let Some(song) = self.log.last_mut() else {
+            return;
+        };
+        self.log_version += 1;
+        match song.buzzes.last_mut() {
+            Some(last) if !new => *last = names,
+            _ => song.buzzes.push(names),
+        }
+    }
+
+    /// Check that no team other than the given one has the
+    /// given name, ignoring case.
+    fn check_unique(&self, name: &str, except: Option<TeamId>) -> Result<(), GameError> {
+        let taken = self
+            .teams
+            .iter()
+            .any(|team| Some(team.id) != except && same_name(&team.name, name));
+        if taken {
+            return Err(GameError::NameTaken(name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Unregister the player the buzzer with the given token is
+    /// registered as, if any.
+    ///
+    /// Unless another buzzer plays as them, the player is removed
+    /// from their team and returned along with the team's ID.
+    fn release_player(&mut self, token: &str) -> Option<(TeamId, Player)> {
+        let name = self.players.remove(token)?;
+        let team = *self.buzzers.get(token)?;
+        let shared = self.players.iter().any(|(other, player)| {
+            *player == name && self.buzzers.get(other) == Some(&team)
+        });
+        if shared {
+            return None;
+        }
+        let entry = self.team_mut(team).ok()?;
+        let index = entry.players.iter().position(|player| player.name == name)?;
+        Some((team, entry.players.remove(index)))
+    }
+
+    /// Count a buzz for the player who pressed it, if known.
+    fn count_buzz(&mut self, record: &BuzzRecord) {
+        if let (Some(player), Ok(team)) = (&record.player, self.team_mut(record.team)) {
+            player_mut(team, player).buzzes += 1;
+        }
+    }
+
+    /// The nickname of the player who buzzed for the given
+    /// guessing team, if known.
+    fn guessing_player(&self, team: TeamId) -> Option<String> {
+        self.buzzes
+            .iter()
+            .find(|buzz| buzz.team == team)
+            .and_then(|buzz| buzz.player.clone())
+    }
+
+    /// Record a guess of the given team for the current song in the log.
+    fn log_guess(&mut self, team: TeamId, title: bool, artist: bool, points: i64) {
+        let Ok(entry) = self.team(team) else {
+            return;
+        };
+        let guess = Guess {
+            team: entry.name.clone(),
+            player: self.guessing_player(team),
+            title,
+            artist,
+            points,
+            after: self.buzzed_after.as_millis() as u64,
+        };
+        if let Some(song) = self.log.last_mut() {
+            song.guesses.push(guess);
+            self.log_version += 1;
+        }
+    }
+
+    /// Record the points each team scored in the current round.
+    fn end_round(&mut self) {
+        let earlier = &self.rounds[..self.round];
+        let points = self
+            .teams
+            .iter()
+            .map(|team| {
+                let before: i64 = earlier
+                    .iter()
+                    .map(|round| round.points.get(&team.id).copied().unwrap_or(0))
+                    .sum();
+                (team.id, team.points - before)
+            })
+            .collect();
+        let round = &mut self.rounds[self.round];
+        info!("Round {:?} ended: {:?}", round.name, points);
+        round.points = points;
+    }
+
+    /// Start a new listening turn with its limit and the end
+    /// of its snippet, if any.
+    ///
+    /// Once the snippet is over, nothing plays which could be
+    /// limited, so the turn has no timers.
+    fn start_listening(&mut self) -> Vec<Effect> {
+        self.turn += 1;
+        if self.snippet_over {
+            return Vec::new();
+        }
+        let limit = self.listening_time.map(|time| Effect::LimitListening {
+            turn: self.turn,
+            time,
+        });
+        let snippet = self.snippet.map(|time| Effect::EndSnippet {
+            turn: self.turn,
+            time,
+        });
+        limit.into_iter().chain(snippet).collect()
+    }
+
+    /// Resume playback after a guess, unless the remaining
+    /// teams keep listening to a snippet which is over.
+    fn resume(&self) -> Option<Effect> {
+        match self.phase {
+            Phase::Listening { .. } if self.snippet_over => None,
+            _ => Some(Effect::Resume),
+        }
+    }
+
+    /// Restore the state before the last applied event.
+    ///
+    /// Playback is paused or resumed if the song played in
+    /// that state but does not now, or the other way round.
+    fn undo(&mut self) -> Result<Vec<Effect>, GameError> {
+        let mut previous = self.history.pop().ok_or(GameError::NothingToUndo)?;
+        previous.history = std::mem::take(&mut self.history);
+        let mut effects = match (self.playing, previous.playing) {
+            (true, false) => vec![Effect::Pause],
+            (false, true) => vec![Effect::Resume],
+            _ => vec![],
+        };
+        let removed: Vec<TeamId> = self
+            .teams
+            .iter()
+            .map(|team| team.id)
+            .filter(|id| previous.team(*id).is_err())
+            .collect();
+        // Registrations made since stay valid as long as their
+        // team exists, and those of teams deleted since are
+        // restored along with the team.
+        let mut buzzers = std::mem::take(&mut previous.buzzers);
+        let mut players = std::mem::take(&mut previous.players);
+        for (token, team) in std::mem::take(&mut self.buzzers) {
+            match self.players.remove(&token) {
+                Some(player) => players.insert(token.clone(), player),
+                None => players.remove(&token),
+            };
+            buzzers.insert(token, team);
+        }
+        buzzers.retain(|_, team| previous.team(*team).is_ok());
+        players.retain(|token, _| buzzers.contains_key(token));
+        previous.buzzers = buzzers;
+        previous.players = players;
+        // Teams created by buzzers were carried over, so their
+        // creators are as well.
+        let mut creators = std::mem::take(&mut self.creators);
+        creators.retain(|_, team| previous.team(*team).is_ok());
+        previous.creators.extend(creators);
+        info!("Undoing last event");
+        previous.turn = self.turn;
+        // The restored log may differ from the current one,
+        // so it counts as another change.
+        previous.log_version = self.log_version + 1;
+        // IDs of teams created since are never given out again.
+        previous.next_team = self.next_team;
+        *self = previous;
+        // Timers of the turns since have been invalidated, so
+        // a restored turn gets a fresh one.
+        effects.extend(self.restart_timers());
+        effects.push(Effect::Broadcast(Update { removed }));
+        Ok(effects)
+    }
+
+    /// The buzzes of the current buzz round, fastest first.
+    pub fn buzzes(&self) -> Vec<Buzz> {
+        let Some(fastest) = self.buzzes.first() else {
+            return Vec::new();
+        };
+        let fastest = fastest.pressed(self.tie_window);
+        self.buzzes
+            .iter()
+            .map(|buzz| Buzz {
+                team: buzz.team,
+                player: buzz.player.clone(),
+                delay: (buzz.pressed(self.tie_window) - fastest).as_millis() as u64,
+            })
+            .collect()
+    }
+
+    fn select(
+        &mut self,
+        id: String,
+        track: Track,
+        start: Duration,
+        snippet: Option<Duration>,
+        at: u64,
+    ) -> Result<Vec<Effect>, GameError> {
+        let Phase::Selection = self.phase else {
+            return Err(GameError::WrongPhase);
+        };
+        if let Some(round) = self.rounds.get(self.round) {
+            if self.played >= round.songs {
+                return Err(GameError::RoundComplete);
+            }
+            self.played += 1;
+        }
+        self.log_version += 1;
+        self.log.push(PlayedSong {
+            track: track.clone(),
+            round: self.rounds.get(self.round).map(|_| self.round),
+            started: at,
+            buzzes: Vec::new(),
+            guesses: Vec::new(),
+        });
+        self.song.replace(track);
+        self.buzzes.clear();
+        self.snippet = snippet;
+        self.snippet_over = false;
+        self.phase = Phase::Listening {
+            active: self.teams.iter().map(|team| team.id).collect(),
+            solved: Default::default(),
+        };
+        let mut effects = vec![Effect::Play {
+            id,
+            position: start,
+        }];
+        effects.extend(self.start_listening());
+        effects.push(Effect::Broadcast(Default::default()));
+        Ok(effects)
+    }
+
+    /// Let the remaining active teams keep listening after a guess
+    /// and return the timers of their listening turn.
+    ///
+    /// If no team is left, the song is over.
+    fn continue_without(&mut self, team: TeamId, active: &[TeamId], solved: Solved) -> Vec<Effect> {
+        let actives = active
+            .iter()
+            .copied()
+            .filter(|active| *active != team)
+            .collect::<Vec<TeamId>>();
+        if !actives.is_empty() {
+            self.phase = Phase::Listening {
+                active: actives,
+                solved,
+            };
+            self.start_listening()
+        } else {
+            self.phase = Phase::Selection;
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn game(teams: usize) -> Game {
+        let mut game = Game::default();
+        for n in 0..teams {
+            game.apply(Event::CreateTeam {
+                name: format!("Team {n}"),
+            })
+            .unwrap();
+        }
+        game
+    }
+
+    fn select() -> Event {
+        Event::Select {
+            id: "spotify:track:test".to_string(),
+            track: Track::default(),
+            start: Duration::ZERO,
+            snippet: None,
+            at: 0,
+        }
+    }
+
+    fn buzz(team: TeamId) -> Event {
+        Event::Buzz {
+            team,
+            player: None,
+            played: Duration::ZERO,
+            received: Duration::ZERO,
+            latency: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn select_starts_listening_for_all_teams() {
+        let mut game = game(2);
+        let effects = game.apply(select()).unwrap();
+        assert_eq!(
+            game.phase,
+            Phase::Listening {
+                active: vec![0, 1],
+                solved: Default::default()
+            }
+        );
+        assert_eq!(
+            effects,
+            vec![
+                Effect::Play {
+                    id: "spotify:track:test".to_string(),
+                    position: Duration::ZERO
+                },
+                Effect::Broadcast(Default::default())
+            ]
+        );
+    }
+
+    #[test]
+    fn buzz_pauses_and_starts_guessing() {
+        let mut game = game(2);
+        game.apply(select()).unwrap();
+        let effects = game.apply(buzz(1)).unwrap();
+        assert_eq!(
+            game.phase,
+            Phase::Guessing {
+                active: vec![0, 1],
+                team: 1,
+                solved: Default::default(),
+                queue: vec![]
+            }
+        );
+        assert_eq!(effects[0], Effect::Pause);
+    }
+
+    #[test]
+    fn accept_awards_point() {
+        let mut game = game(2);
+        game.apply(select()).unwrap();
+        game.apply(buzz(1)).unwrap();
+        game.apply(Event::Accept {
+            title: true,
+            artist: true,
+        })
+        .unwrap();
+        assert_eq!(game.phase, Phase::Selection);
+        assert_eq!(game.teams[0].points, 0);
+        assert_eq!(game.teams[1].points, 1);
+    }
+
+    #[test]
+    fn partial_guess_lets_others_steal() {
+        let mut game = game(3);
+        game.apply(Event::SetScoring {
+            rules: ScoringRules::preset("split").unwrap(),
+        })
+        .unwrap();
+        game.apply(select()).unwrap();
+        game.apply(buzz(0)).unwrap();
+        game.apply(Event::Accept {
+            title: true,
+            artist: false,
+        })
+        .unwrap();
+        let solved = Solved {
+            title: true,
+            artist: false,
+        };
+        assert_eq!(
+            game.phase,
+            Phase::Listening {
+                active: vec![1, 2],
+                solved
+            }
+        );
+        game.apply(buzz(2)).unwrap();
+        assert_eq!(
+            game.apply(Event::Accept {
+                title: true,
+                artist: false
+            }),
+            Err(GameError::AlreadySolved)
+        );
+        game.apply(Event::Accept {
+            title: true,
+            artist: true,
+        })
+        .unwrap();
+        assert_eq!(game.phase, Phase::Selection);
+        assert_eq!(game.teams[0].points, 1);
+        assert_eq!(game.teams[1].points, 0);
+        assert_eq!(game.teams[2].points, 1);
+    }
+
+    #[test]
+    fn close_buzzes_are_ordered_by_latency() {
+        let mut game = game(3);
+        game.tie_window = Duration::from_millis(100);
+        game.apply(select()).unwrap();
+        let buzz = |team, received, latency| Event::Buzz {
+            team,
+            player: None,
+            played: Duration::from_millis(received),
+            received: Duration::from_millis(received),
+            latency: Duration::from_millis(latency),
+        };
+        game.apply(buzz(0, 1000, 10)).unwrap();
+        let effects = game.apply(buzz(1, 1040, 80)).unwrap();
+        assert_eq!(effects, vec![Effect::Broadcast(Default::default())]);
+        assert_eq!(
+            game.apply(buzz(1, 1050, 0)),
+            Err(GameError::Inactive(1))
+        );
+        // Team 1 wins, but the window still ends 100ms after
+        // the buzz of team 0 arrived.
+        assert_eq!(game.apply(buzz(2, 1120, 0)), Err(GameError::WrongPhase));
+        assert!(matches!(game.phase, Phase::Guessing { team: 1, .. }));
+        assert_eq!(game.buzzed_after, Duration::from_millis(960));
+        assert_eq!(
+            game.buzzes(),
+            vec![
+                Buzz {
+                    team: 1,
+                    delay: 0,
+                    player: None
+                },
+                Buzz {
+                    team: 0,
+                    delay: 30,
+                    player: None
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn rejection_passes_to_queued_team() {
+        let mut game = game(3);
+        game.apply(Event::SetBuzzQueue { enabled: true }).unwrap();
+        game.apply(select()).unwrap();
+        game.apply(buzz(2)).unwrap();
+        let late = |team| Event::Buzz {
+            team,
+            player: None,
+            played: Duration::ZERO,
+            received: Duration::from_secs(1),
+            latency: Duration::ZERO,
+        };
+        game.apply(late(0)).unwrap();
+        assert_eq!(game.apply(late(0)), Err(GameError::Inactive(0)));
+        let effects = game.apply(Event::RejectGuess).unwrap();
+        assert_eq!(effects, vec![Effect::Broadcast(Default::default())]);
+        assert_eq!(
+            game.phase,
+            Phase::Guessing {
+                active: vec![0, 1],
+                team: 0,
+                solved: Default::default(),
+                queue: vec![]
+            }
+        );
+        let effects = game.apply(Event::RejectGuess).unwrap();
+        assert_eq!(effects[0], Effect::Resume);
+        assert_eq!(
+            game.phase,
+            Phase::Listening {
+                active: vec![1],
+                solved: Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn answer_timer_rejects_current_turn_only() {
+        let mut game = game(2);
+        game.apply(Event::SetAnswerTime {
+            time: Some(Duration::from_secs(10)),
+        })
+        .unwrap();
+        game.apply(select()).unwrap();
+        let effects = game.apply(buzz(0)).unwrap();
+        let turn = game.turn;
+        assert!(effects.contains(&Effect::StartTimer {
+            turn,
+            time: Duration::from_secs(10)
+        }));
+        assert_eq!(
+            game.apply(Event::TimeUp { turn: turn - 1 }),
+            Err(GameError::StaleTimer)
+        );
+        game.apply(Event::TimeUp { turn }).unwrap();
+        assert_eq!(
+            game.phase,
+            Phase::Listening {
+                active: vec![1],
+                solved: Default::default()
+            }
+        );
+        assert_eq!(
+            game.apply(Event::TimeUp { turn }),
+            Err(GameError::StaleTimer)
+        );
+    }
+
+    #[test]
+    fn answer_time_changes_apply_to_current_turn() {
+        let mut game = game(2);
+        game.apply(Event::SetAnswerTime {
+            time: Some(Duration::from_secs(10)),
+        })
+        .unwrap();
+        game.apply(select()).unwrap();
+        game.apply(buzz(0)).unwrap();
+        let turn = game.turn;
+        game.apply(Event::SetAnswerTime { time: None }).unwrap();
+        assert_eq!(
+            game.apply(Event::TimeUp { turn }),
+            Err(GameError::StaleTimer)
+        );
+        let effects = game
+            .apply(Event::SetAnswerTime {
+                time: Some(Duration::from_secs(5)),
+            })
+            .unwrap();
+        assert!(effects.contains(&Effect::StartTimer {
+            turn: game.turn,
+            time: Duration::from_secs(5)
+        }));
+        assert_eq!(
+            game.apply(Event::TimeUp { turn }),
+            Err(GameError::StaleTimer)
+        );
+        let effects = game.restart_timers();
+        assert_eq!(
+            effects,
+            vec![Effect::StartTimer {
+                turn: game.turn,
+                time: Duration::from_secs(5)
+            }]
+        );
+        game.apply(Event::TimeUp { turn: game.turn }).unwrap();
+        assert!(matches!(game.phase, Phase::Listening { .. }));
+    }
+
+    #[test]
+    fn listening_limit_reveals_song() {
+        let mut game = game(2);
+        game.apply(Event::SetListeningTime {
+            time: Some(Duration::from_secs(30)),
+        })
+        .unwrap();
+        let effects = game.apply(select()).unwrap();
+        let first = game.turn;
+        assert!(effects.contains(&Effect::LimitListening {
+            turn: first,
+            time: Duration::from_secs(30)
+        }));
+        game.apply(buzz(0)).unwrap();
+        let effects = game.apply(Event::RejectGuess).unwrap();
+        let turn = game.turn;
+        assert!(effects.contains(&Effect::LimitListening {
+            turn,
+            time: Duration::from_secs(30)
+        }));
+        assert_eq!(
+            game.apply(Event::ListeningTimeUp { turn: first }),
+            Err(GameError::StaleTimer)
+        );
+        let effects = game.apply(Event::ListeningTimeUp { turn }).unwrap();
+        assert_eq!(effects[0], Effect::Pause);
+        assert_eq!(game.phase, Phase::Selection);
+        assert!(!game.playing);
+
+        // Undoing the reveal plays the song on with a fresh limit.
+        let effects = game.apply(Event::Undo).unwrap();
+        assert_eq!(effects[0], Effect::Resume);
+        assert!(matches!(game.phase, Phase::Listening { .. }));
+        assert!(game.playing);
+        assert!(effects.contains(&Effect::LimitListening {
+            turn: game.turn,
+            time: Duration::from_secs(30)
+        }));
+    }
+
+    #[test]
+    fn listening_limit_can_be_lifted() {
+        let mut game = game(1);
+        game.apply(Event::SetListeningTime {
+            time: Some(Duration::from_secs(30)),
+        })
+        .unwrap();
+        game.apply(select()).unwrap();
+        let turn = game.turn;
+        game.apply(Event::SetListeningTime { time: None }).unwrap();
+        assert_eq!(
+            game.apply(Event::ListeningTimeUp { turn }),
+            Err(GameError::StaleTimer)
+        );
+        let effects = game
+            .apply(Event::SetListeningTime {
+                time: Some(Duration::from_secs(20)),
+            })
+            .unwrap();
+        assert!(effects.contains(&Effect::LimitListening {
+            turn: game.turn,
+            time: Duration::from_secs(20)
+        }));
+        assert!(matches!(game.phase, Phase::Listening { .. }));
+    }
+
+    #[test]
+    fn snippet_stays_paused_after_guess() {
+        let mut game = game(2);
+        let effects = game
+            .apply(Event::Select {
+                id: "spotify:track:test".to_string(),
+                track: Track::default(),
+                start: Duration::from_secs(60),
+                snippet: Some(Duration::from_secs(10)),
+                at: 0,
+            })
+            .unwrap();
+        assert_eq!(
+            effects[0],
+            Effect::Play {
+                id: "spotify:track:test".to_string(),
+                position: Duration::from_secs(60)
+            }
+        );
+        assert!(effects.contains(&Effect::EndSnippet {
+            turn: game.turn,
+            time: Duration::from_secs(10)
+        }));
+        let effects = game.apply(Event::SnippetOver { turn: game.turn }).unwrap();
+        assert_eq!(effects[0], Effect::Pause);
+        game.apply(buzz(0)).unwrap();
+        let effects = game.apply(Event::RejectGuess).unwrap();
+        assert_eq!(effects, vec![Effect::Broadcast(Default::default())]);
+        assert!(matches!(game.phase, Phase::Listening { .. }));
+    }
+
+    #[test]
+    fn snippet_end_suspends_listening_limit() {
+        let mut game = game(1);
+        game.apply(Event::SetListeningTime {
+            time: Some(Duration::from_secs(30)),
+        })
+        .unwrap();
+        game.apply(Event::Select {
+            id: "spotify:track:test".to_string(),
+            track: Track::default(),
+            start: Duration::ZERO,
+            snippet: Some(Duration::from_secs(10)),
+            at: 0,
+        })
+        .unwrap();
+        let turn = game.turn;
+        let effects = game.apply(Event::SnippetOver { turn }).unwrap();
+        assert_eq!(
+            effects,
+            vec![Effect::Pause, Effect::Broadcast(Default::default())]
+        );
+        assert_eq!(
+            game.apply(Event::ListeningTimeUp { turn }),
+            Err(GameError::StaleTimer)
+        );
+        // Undoing the end of the snippet plays it on, with
+        // fresh timers.
+        let effects = game.apply(Event::Undo).unwrap();
+        assert_eq!(effects[0], Effect::Resume);
+        assert!(effects.contains(&Effect::LimitListening {
+            turn: game.turn,
+            time: Duration::from_secs(30)
+        }));
+        assert!(!game.snippet_over);
+    }
+
+    #[test]
+    fn reject_deactivates_team() {
+        let mut game = game(2);
+        game.apply(select()).unwrap();
+        game.apply(buzz(0)).unwrap();
+        game.apply(Event::RejectGuess).unwrap();
+        assert_eq!(
+            game.phase,
+            Phase::Listening {
+                active: vec![1],
+                solved: Default::default()
+            }
+        );
+        assert_eq!(
+            game.apply(buzz(0)),
+            Err(GameError::Inactive(0))
+        );
+        game.apply(buzz(1)).unwrap();
+        game.apply(Event::RejectGuess).unwrap();
+        assert_eq!(game.phase, Phase::Selection);
+    }
+
+    #[test]
+    fn teams_can_be_edited_during_a_song() {
+        let mut game = game(3);
+        game.apply(Event::SetBuzzQueue { enabled: true }).unwrap();
+        game.apply(select()).unwrap();
+        game.apply(Event::CreateTeam {
+            name: "Late".to_string(),
+        })
+        .unwrap();
+        assert!(!game.phase.is_active(3));
+        assert_eq!(game.apply(buzz(3)), Err(GameError::Inactive(3)));
+        game.apply(buzz(0)).unwrap();
+        game.apply(Event::Buzz {
+            team: 1,
+            player: None,
+            played: Duration::ZERO,
+            received: Duration::from_secs(1),
+            latency: Duration::ZERO,
+        })
+        .unwrap();
+        game.apply(Event::RenameTeam {
+            team: 1,
+            name: "Renamed".to_string(),
+        })
+        .unwrap();
+        // The guessing team leaves without losing points, so
+        // the queued team answers next.
+        game.apply(Event::DeleteTeam { team: 0 }).unwrap();
+        assert_eq!(
+            game.phase,
+            Phase::Guessing {
+                active: vec![1, 2],
+                team: 1,
+                solved: Default::default(),
+                queue: Vec::new(),
+            }
+        );
+        let effects = game.apply(Event::DeleteTeam { team: 1 }).unwrap();
+        assert!(effects.contains(&Effect::Resume));
+        assert_eq!(
+            game.phase,
+            Phase::Listening {
+                active: vec![2],
+                solved: Default::default()
+            }
+        );
+        game.apply(Event::DeleteTeam { team: 2 }).unwrap();
+        assert_eq!(game.phase, Phase::Selection);
+    }
+
+    #[test]
+    fn team_names_are_unique() {
+        let mut game = game(2);
+        assert_eq!(
+            game.apply(Event::CreateTeam {
+                name: "Team 0".to_string()
+            }),
+            Err(GameError::NameTaken("Team 0".to_string()))
+        );
+        assert_eq!(
+            game.apply(Event::RenameTeam {
+                team: 1,
+                name: "team 0".to_string()
+            }),
+            Err(GameError::NameTaken("team 0".to_string()))
+        );
+        game.apply(Event::RenameTeam {
+            team: 0,
+            name: "TEAM 0".to_string(),
+        })
+        .unwrap();
+        assert_eq!(game.teams.len(), 2);
+    }
+
+    #[test]
+    fn delete_removes_only_the_team() {
+        let mut game = game(2);
+        let effects = game.apply(Event::DeleteTeam { team: 0 }).unwrap();
+        assert_eq!(game.teams.len(), 1);
+        assert_eq!(game.teams[0].id, 1);
+        assert_eq!(
+            effects,
+            vec![Effect::Broadcast(Update { removed: vec![0] })]
+        );
+        assert_eq!(
+            game.apply(Event::DeleteTeam { team: 0 }),
+            Err(GameError::UnknownTeam(0))
+        );
+        game.apply(Event::CreateTeam {
+            name: "Team 2".to_string(),
+        })
+        .unwrap();
+        assert_eq!(game.teams[1].id, 2);
+    }
+
+    #[test]
+    fn scoring_rules_apply() {
+        let mut game = game(2);
+        game.apply(Event::SetScoring {
+            rules: ScoringRules::preset("strict").unwrap(),
+        })
+        .unwrap();
+        game.apply(select()).unwrap();
+        game.apply(buzz(0)).unwrap();
+        game.apply(Event::RejectGuess).unwrap();
+        game.apply(Event::Buzz {
+            team: 1,
+            player: None,
+            played: Duration::from_secs(20),
+            received: Duration::from_secs(20),
+            latency: Duration::ZERO,
+        })
+        .unwrap();
+        game.apply(Event::Accept {
+            title: true,
+            artist: true,
+        })
+        .unwrap();
+        assert_eq!(game.teams[0].points, -1);
+        assert_eq!(game.teams[1].points, 2);
+    }
+
+    fn entry(name: &str) -> LibraryEntry {
+        LibraryEntry {
+            id: format!("spotify:track:{name}"),
+            track: Track {
+                name: name.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn queue_is_played_in_order() {
+        let mut game = game(1);
+        game.apply(Event::Enqueue {
+            entries: vec![entry("a"), entry("b"), entry("c")],
+        })
+        .unwrap();
+        game.apply(Event::MoveQueueEntry { from: 2, to: 0 }).unwrap();
+        game.apply(Event::RemoveQueueEntry { index: 1 }).unwrap();
+        let effects = game.apply(Event::PlayNext { at: 0 }).unwrap();
+        assert_eq!(
+            effects[0],
+            Effect::Play {
+                id: "spotify:track:c".to_string(),
+                position: Duration::ZERO
+            }
+        );
+        assert_eq!(game.song.as_ref().unwrap().name, "c");
+        assert_eq!(game.queue, vec![entry("b")]);
+        assert_eq!(game.apply(Event::PlayNext { at: 0 }), Err(GameError::WrongPhase));
+    }
+
+    #[test]
+    fn points_can_be_corrected() {
+        let mut game = game(2);
+        game.apply(Event::AdjustPoints { team: 0, delta: 3 }).unwrap();
+        game.apply(Event::AdjustPoints { team: 0, delta: -1 }).unwrap();
+        game.apply(Event::SetPoints { team: 1, points: -4 }).unwrap();
+        assert_eq!(game.teams[0].points, 2);
+        assert_eq!(game.teams[1].points, -4);
+        assert_eq!(
+            game.apply(Event::SetPoints { team: 2, points: 1 }),
+            Err(GameError::UnknownTeam(2))
+        );
+    }
+
+    #[test]
+    fn undo_reverts_accept() {
+        let mut game = game(2);
+        game.apply(select()).unwrap();
+        game.apply(buzz(1)).unwrap();
+        let guessing = game.clone();
+        game.apply(Event::Accept {
+            title: true,
+            artist: true,
+        })
+        .unwrap();
+        let effects = game.apply(Event::Undo).unwrap();
+        assert_eq!(game.phase, guessing.phase);
+        assert_eq!(game.teams, guessing.teams);
+        assert_eq!(effects[0], Effect::Pause);
+        game.apply(Event::Undo).unwrap();
+        assert_eq!(
+            game.phase,
+            Phase::Listening {
+                active: vec![0, 1],
+                solved: Default::default()
+            }
+        );
+        assert_eq!(game.apply(Event::Undo), Err(GameError::NothingToUndo));
+    }
+
+    #[test]
+    fn registrations_survive_deleting_other_teams() {
+        let mut game = game(3);
+        for (token, team) in [("a", 0), ("b", 1), ("c", 2)] {
+            game.apply(Event::Register {
+                token: token.to_string(),
+                team,
+                player: None,
+            })
+            .unwrap();
+        }
+        assert_eq!(
+            game.apply(Event::Register {
+                token: "d".to_string(),
+                team: 3,
+                player: None,
+            }),
+            Err(GameError::UnknownTeam(3))
+        );
+        game.apply(Event::DeleteTeam { team: 1 }).unwrap();
+        assert_eq!(game.buzzers.get("a"), Some(&0));
+        assert_eq!(game.buzzers.get("b"), None);
+        assert_eq!(game.buzzers.get("c"), Some(&2));
+        let effects = game.apply(Event::Undo).unwrap();
+        assert!(effects.contains(&Effect::Broadcast(Update::default())));
+        assert_eq!(game.buzzers.get("b"), Some(&1));
+        assert_eq!(game.buzzers.get("c"), Some(&2));
+    }
+
+    #[test]
+    fn log_records_buzzes_and_guesses() {
+        let mut game = game(2);
+        game.apply(select()).unwrap();
+        game.apply(buzz(1)).unwrap();
+        game.apply(Event::RejectGuess).unwrap();
+        game.apply(buzz(0)).unwrap();
+        game.apply(Event::Accept {
+            title: true,
+            artist: false,
+        })
+        .unwrap();
+        assert_eq!(game.log.len(), 1);
+        let song = &game.log[0];
+        assert_eq!(
+            song.buzzes,
+            vec![vec!["Team 1".to_string()], vec!["Team 0".to_string()]]
+        );
+        let guesses: Vec<(&str, bool, bool)> = song
+            .guesses
+            .iter()
+            .map(|guess| (guess.team.as_str(), guess.title, guess.artist))
+            .collect();
+        assert_eq!(guesses, vec![("Team 1", false, false), ("Team 0", true, false)]);
+        // Every change of the log is noticed, including undoing one.
+        let version = game.log_version;
+        game.apply(Event::Undo).unwrap();
+        assert!(game.log_version > version);
+        game.apply(Event::AdjustPoints { team: 0, delta: 1 }).unwrap();
+        assert!(game.log_version > version);
+    }
+
+    #[test]
+    fn buzzers_propose_names() {
+        let mut game = game(1);
+        let propose = |name: &str| Event::ProposeTeam {
+            token: "a".to_string(),
+            name: name.to_string(),
+            placeholder: "Coole Otter".to_string(),
+        };
+        assert_eq!(
+            game.apply(propose(&"x".repeat(40))),
+            Err(GameError::InvalidName(NameProblem::TooLong))
+        );
+        assert_eq!(
+            game.apply(propose("Team 0")),
+            Err(GameError::NameTaken("Team 0".to_string()))
+        );
+        game.apply(propose(" Die Ohrwürmer ")).unwrap();
+        assert_eq!(game.teams[1].name, "Die Ohrwürmer");
+        assert_eq!(game.buzzers.get("a"), Some(&1));
+
+        game.apply(Event::SetNameApproval { enabled: true }).unwrap();
+        game.apply(Event::ProposeName {
+            team: 1,
+            name: "Taktiker".to_string(),
+        })
+        .unwrap();
+        assert_eq!(game.teams[1].name, "Die Ohrwürmer");
+        assert_eq!(game.teams[1].proposed, Some("Taktiker".to_string()));
+        game.apply(Event::ApproveName { team: 1 }).unwrap();
+        assert_eq!(game.teams[1].name, "Taktiker");
+        assert_eq!(
+            game.apply(Event::RejectName { team: 1 }),
+            Err(GameError::NoProposal(1))
+        );
+
+        game.apply(Event::ProposeTeam {
+            token: "b".to_string(),
+            name: "Halbtöne".to_string(),
+            placeholder: "Coole Otter".to_string(),
+        })
+        .unwrap();
+        assert_eq!(game.teams[2].name, "Coole Otter");
+        game.apply(Event::RejectName { team: 2 }).unwrap();
+        assert_eq!(game.teams[2].name, "Coole Otter");
+        assert_eq!(game.teams[2].proposed, None);
+        assert_eq!(
+            game.apply(propose("Noch eins")),
+            Err(GameError::AlreadyCreated(1))
+        );
+
+        // Undoing moves of the admin keeps what buzzers did since.
+        game.apply(Event::Undo).unwrap();
+        assert_eq!(game.teams[2].proposed, Some("Halbtöne".to_string()));
+        game.apply(Event::Undo).unwrap();
+        assert_eq!(game.teams[1].name, "Die Ohrwürmer");
+        assert_eq!(game.teams.len(), 3);
+        assert_eq!(game.buzzers.get("b"), Some(&2));
+        assert_eq!(
+            game.apply(propose("Noch eins")),
+            Err(GameError::AlreadyCreated(1))
+        );
+    }
+
+    #[test]
+    fn team_count_is_limited() {
+        let mut game = game(MAX_TEAMS);
+        assert_eq!(
+            game.apply(Event::CreateTeam {
+                name: "Zu viel".to_string()
+            }),
+            Err(GameError::TooManyTeams)
+        );
+        assert_eq!(
+            game.apply(Event::ProposeTeam {
+                token: "a".to_string(),
+                name: "Zu viel".to_string(),
+                placeholder: "Coole Otter".to_string(),
+            }),
+            Err(GameError::TooManyTeams)
+        );
+    }
+
+    #[test]
+    fn players_keep_statistics() {
+        let mut game = game(2);
+        game.apply(Event::Register {
+            token: "a".to_string(),
+            team: 0,
+            player: Some(" Anna ".to_string()),
+        })
+        .unwrap();
+        assert_eq!(game.players.get("a"), Some(&"Anna".to_string()));
+        game.apply(select()).unwrap();
+        let buzz = |team, player: Option<&str>| Event::Buzz {
+            team,
+            player: player.map(str::to_string),
+            played: Duration::ZERO,
+            received: Duration::ZERO,
+            latency: Duration::ZERO,
+        };
+        game.apply(buzz(0, Some("Anna"))).unwrap();
+        assert_eq!(game.buzzes()[0].player, Some("Anna".to_string()));
+        game.apply(Event::Accept {
+            title: true,
+            artist: false,
+        })
+        .unwrap();
+        game.apply(buzz(1, Some("Ben"))).unwrap();
+        game.apply(Event::RejectGuess).unwrap();
+        let stats = |game: &Game, team: usize| -> Vec<(String, u64, u64)> {
+            game.teams[team]
+                .players
+                .iter()
+                .map(|player| (player.name.clone(), player.buzzes, player.correct))
+                .collect()
+        };
+        assert_eq!(stats(&game, 0), vec![("Anna".to_string(), 1, 1)]);
+        assert_eq!(stats(&game, 1), vec![("Ben".to_string(), 1, 0)]);
+        assert_eq!(game.log[0].guesses[0].player, Some("Anna".to_string()));
+        assert_eq!(
+            game.apply(Event::Register {
+                token: "a".to_string(),
+                team: 0,
+                player: Some("x".repeat(40)),
+            }),
+            Err(GameError::InvalidName(NameProblem::TooLong))
+        );
+        // A new nickname replaces the old one, keeping its statistics.
+        game.apply(Event::Register {
+            token: "a".to_string(),
+            team: 0,
+            player: Some("Anni".to_string()),
+        })
+        .unwrap();
+        assert_eq!(stats(&game, 0), vec![("Anni".to_string(), 1, 1)]);
+        game.apply(Event::Register {
+            token: "a".to_string(),
+            team: 1,
+            player: None,
+        })
+        .unwrap();
+        assert_eq!(game.players.get("a"), None);
+        assert!(game.teams[0].players.is_empty());
+    }
+
+    #[test]
+    fn match_is_played_in_rounds() {
+        let mut game = game(2);
+        let round = |name: &str| Round {
+            name: name.to_string(),
+            songs: 1,
+            points: Default::default(),
+        };
+        game.apply(Event::StartMatch {
+            rounds: vec![round("80er"), round("Intros")],
+        })
+        .unwrap();
+        game.apply(select()).unwrap();
+        game.apply(buzz(1)).unwrap();
+        game.apply(Event::Accept {
+            title: true,
+            artist: true,
+        })
+        .unwrap();
+        assert_eq!(game.apply(select()), Err(GameError::RoundComplete));
+        game.apply(Event::NextRound).unwrap();
+        assert_eq!(game.phase, Phase::RoundOver { round: 0 });
+        assert_eq!(game.rounds[0].points, BTreeMap::from([(0, 0), (1, 1)]));
+        game.apply(Event::NextRound).unwrap();
+        assert_eq!(game.phase, Phase::Selection);
+        game.apply(select()).unwrap();
+        game.apply(buzz(0)).unwrap();
+        game.apply(Event::Accept {
+            title: true,
+            artist: true,
+        })
+        .unwrap();
+        game.apply(Event::FinishMatch).unwrap();
+        assert_eq!(game.phase, Phase::Finished);
+        assert_eq!(game.rounds[1].points, BTreeMap::from([(0, 1), (1, 0)]));
+        assert_eq!(game.apply(Event::NextRound), Err(GameError::WrongPhase));
+        game.apply(Event::LeaveMatch).unwrap();
+        assert_eq!(game.phase, Phase::Selection);
+        game.apply(select()).unwrap();
+        assert_eq!(game.log.last().unwrap().round, None);
+        assert_eq!(game.rounds[1].points, BTreeMap::from([(0, 1), (1, 0)]));
+    }
+
+    fn event() -> impl Strategy<Value = Event> {
+        prop_oneof![
+            Just(select()),
+            (0..6u64, 0..120u64, 0..300u64, 0..300u64).prop_map(
+                |(team, played, received, latency)| Event::Buzz {
+                    team,
+                    player: None,
+                    played: Duration::from_secs(played),
+                    received: Duration::from_millis(received),
+                    latency: Duration::from_millis(latency),
+                }
+            ),
+            Just(Event::StopListening),
+            (any::<bool>(), any::<bool>())
+                .prop_map(|(title, artist)| Event::Accept { title, artist }),
+            Just(Event::RejectGuess),
+            "[a-z]{1,8}".prop_map(|name| Event::CreateTeam { name }),
+            (0..6u64, "[a-z]{1,8}").prop_map(|(team, name)| Event::RenameTeam { team, name }),
+            (0..6u64).prop_map(|team| Event::DeleteTeam { team }),
+            "[a-z]{1,8}".prop_map(|name| Event::Enqueue {
+                entries: vec![entry(&name)]
+            }),
+            (0..4usize, 0..4usize).prop_map(|(from, to)| Event::MoveQueueEntry { from, to }),
+            (0..4usize).prop_map(|index| Event::RemoveQueueEntry { index }),
+            Just(Event::PlayNext { at: 0 }),
+            Just(Event::Undo),
+            any::<bool>().prop_map(|enabled| Event::SetBuzzQueue { enabled }),
+            prop::option::of("[a-z]{1,8}").prop_map(|pack| Event::SetNamePack { pack }),
+            ("[a-c]", "[a-z]{0,8}", "[a-z]{1,8}").prop_map(|(token, name, placeholder)| {
+                Event::ProposeTeam {
+                    token,
+                    name,
+                    placeholder,
+                }
+            }),
+            (0..6u64, "[a-z]{0,8}").prop_map(|(team, name)| Event::ProposeName { team, name }),
+            (0..6u64).prop_map(|team| Event::ApproveName { team }),
+            (0..6u64).prop_map(|team| Event::RejectName { team }),
+            any::<bool>().prop_map(|enabled| Event::SetNameApproval { enabled }),
+            prop::option::of(1..60u64).prop_map(|time| Event::SetAnswerTime {
+                time: time.map(Duration::from_secs)
+            }),
+            (0..8u64).prop_map(|turn| Event::TimeUp { turn }),
+            (0..8u64).prop_map(|turn| Event::ListeningTimeUp { turn }),
+            (0..8u64).prop_map(|turn| Event::SnippetOver { turn }),
+            prop::option::of(0..60u64).prop_map(|seconds| Event::SetListeningTime {
+                time: seconds.map(Duration::from_secs)
+            }),
+            ("[a-c]", 0..6u64, prop::option::of("[a-z]{0,4}"))
+                .prop_map(|(token, team, player)| Event::Register { token, team, player }),
+            prop::collection::vec(0..3usize, 0..3).prop_map(|songs| Event::StartMatch {
+                rounds: songs
+                    .into_iter()
+                    .map(|songs| Round {
+                        name: format!("{songs} Songs"),
+                        songs,
+                        points: Default::default(),
+                    })
+                    .collect()
+            }),
+            Just(Event::NextRound),
+            Just(Event::FinishMatch),
+            Just(Event::LeaveMatch),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn failed_events_leave_game_unchanged(events in prop::collection::vec(event(), 0..64)) {
+            let mut game = Game::default();
+            for event in events {
+                let before = game.clone();
+                if game.apply(event).is_err() {
+                    prop_assert_eq!(&game, &before);
+                }
+            }
+        }
+
+        #[test]
+        fn phase_always_matches_teams(events in prop::collection::vec(event(), 0..64)) {
+            let mut game = Game::default();
+            let mut accepted = 0;
+            for event in events {
+                let accept = matches!(event, Event::Accept { .. });
+                if game.apply(event).is_ok() && accept {
+                    accepted += 1;
+                }
+                match &game.phase {
+                    Phase::Selection | Phase::RoundOver { .. } | Phase::Finished => (),
+                    Phase::Listening { active, .. } => {
+                        prop_assert!(active.iter().all(|active| game.team(*active).is_ok()));
+                    }
+                    Phase::Guessing { active, team, queue, .. } => {
+                        prop_assert!(!game.playing);
+                        prop_assert!(active.iter().all(|active| game.team(*active).is_ok()));
+                        prop_assert!(active.contains(team));
+                        prop_assert!(queue.iter().all(|queued| active.contains(queued) && queued != team));
+                    }
+                }
+                prop_assert!(game.buzzers.values().all(|team| game.team(*team).is_ok()));
+                let mut ids: Vec<TeamId> = game.teams.iter().map(|team| team.id).collect();
+                ids.sort();
+                ids.dedup();
+                prop_assert_eq!(ids.len(), game.teams.len());
+                prop_assert!(game.players.keys().all(|token| game.buzzers.contains_key(token)));
+                prop_assert!(game.creators.values().all(|team| game.team(*team).is_ok()));
+                let points: i64 = game.teams.iter().map(|team| team.points).sum();
+                prop_assert!(points <= accepted);
+            }
+        }
+    }
 }
@@ -0,0 +1,58 @@
+pub mod admin;
+pub mod auth;
+pub mod buzzer;
+pub mod export;
+pub mod game;
+pub mod names;
+pub mod persistence;
+pub mod playback;
+pub mod quiz;
+pub mod room;
+pub mod spectator;
+
+use std::sync::Arc;
+
+use admin::admin_upgrade;
+use auth::{login, Auth};
+use axum::{
+    extract::FromRef,
+    routing::{any, delete, post},
+    Router,
+};
+use buzzer::buzzer_upgrade;
+use room::{close_room, create_room, Rooms};
+use spectator::spectator_upgrade;
+
+/// The state shared by all handlers.
+#[derive(Debug, Clone)]
+pub struct AppState {
+    pub rooms: Arc<Rooms>,
+    pub auth: Arc<Auth>,
+}
+
+impl FromRef<AppState> for Arc<Rooms> {
+    fn from_ref(state: &AppState) -> Self {
+        Arc::clone(&state.rooms)
+    }
+}
+
+impl FromRef<AppState> for Arc<Auth> {
+    fn from_ref(state: &AppState) -> Self {
+        Arc::clone(&state.auth)
+    }
+}
+
+/// The websocket endpoints of every room and the
+/// endpoints to log in and to create or close a room.
+///
+/// These have to be served with connect info,
+/// as every handler logs the address of its peer.
+pub fn websocket_router() -> Router<AppState> {
+    Router::new()
+        .route("/login", post(login))
+        .route("/rooms", post(create_room))
+        .route("/rooms/{room}", delete(close_room))
+        .route("/ws/{room}/buzzer", any(buzzer_upgrade))
+        .route("/ws/{room}/admin", any(admin_upgrade))
+        .route("/ws/{room}/spectator", any(spectator_upgrade))
+}
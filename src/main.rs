@@ -1,31 +1,30 @@
-mod admin;
-mod buzzer;
-mod game;
-mod names;
-mod spectator;
+use std::{collections::HashMap, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 
-use std::{collections::HashMap, net::SocketAddr, sync::Arc};
-
-use admin::admin_upgrade;
 use anyhow::anyhow;
 use argh::{from_env, FromArgs};
 use axum::{
     extract::{Path, Query, State},
     http::{header, StatusCode},
     response::{IntoResponse, Redirect},
-    routing::{any, get},
+    routing::get,
     Router,
 };
-use buzzer::buzzer_upgrade;
-use game::{Game, Update};
+use axum_extra::{headers::Cookie, TypedHeader};
 use lazy_static::lazy_static;
-use rspotify::{prelude::OAuthClient, scopes, AuthCodeSpotify, Credentials, OAuth};
+use log::{info, warn};
+use rspotify::{scopes, AuthCodeSpotify, Credentials, OAuth};
 use serde::Deserialize;
-use spectator::spectator_upgrade;
-use tokio::{
-    net::TcpListener,
-    sync::{broadcast::channel, RwLock},
+use songquiz::{
+    auth::Auth,
+    export::{results_csv, results_json},
+    names::Names,
+    persistence,
+    playback::{self, local::Library, LocalBackend, PlaybackBackend, SpotifyBackend},
+    room::Rooms,
+    websocket_router, AppState,
 };
+use tokio::net::TcpListener;
+use types::scoring::ScoringRules;
 use url::Url;
 
 #[derive(FromArgs, PartialEq, Debug)]
@@ -33,10 +32,37 @@ use url::Url;
 struct Args {
     /// client id
     #[argh(option)]
-    client_id: String,
+    client_id: Option<String>,
     /// client secret
     #[argh(option)]
-    client_secret: String,
+    client_secret: Option<String>,
+    /// play songs from this local music directory instead of Spotify
+    #[argh(option)]
+    music_dir: Option<PathBuf>,
+    /// snapshot every room to a file in this directory on every change
+    #[argh(option)]
+    state_dir: Option<PathBuf>,
+    /// resume all rooms from the snapshots in the state directory
+    #[argh(switch)]
+    resume: bool,
+    /// secret admins log in with, generated at startup if not given
+    #[argh(option)]
+    admin_secret: Option<String>,
+    /// scoring preset of new rooms: classic, split, strict or speed
+    #[argh(option)]
+    scoring: Option<ScoringRules>,
+    /// seconds teams in new rooms may take to answer
+    #[argh(option)]
+    answer_time: Option<u64>,
+    /// seconds songs in new rooms may be listened to before they are revealed
+    #[argh(option)]
+    listening_time: Option<u64>,
+    /// JSON file with the name packs teams are named from
+    #[argh(option)]
+    name_packs: Option<PathBuf>,
+    /// milliseconds within which buzzes are ordered by latency-compensated time
+    #[argh(option)]
+    tie_window: Option<u64>,
     /// base url this will be served from
     #[argh(option)]
     base_url: String,
@@ -57,39 +83,83 @@ fn authorize_url(url: &str) -> Option<String> {
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    env_logger::init();
+    // Log at least at info level, so the generated admin
+    // secret is shown.
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     let args: Args = from_env();
-    let redirect = authorize_url(&args.base_url).ok_or(anyhow!("got invalid base url"))?;
-    let spotify = AuthCodeSpotify::new(
-        Credentials::new(&args.client_id, &args.client_secret),
-        OAuth {
-            redirect_uri: redirect,
-            scopes: scopes!("user-modify-playback-state"),
-            ..Default::default()
+    let mut app = Router::new();
+    let playback: Arc<dyn PlaybackBackend> = if let Some(dir) = &args.music_dir {
+        let library = Arc::new(Library::scan(dir)?);
+        app = app.merge(playback::local::router(Arc::clone(&library)));
+        Arc::new(LocalBackend::new(library))
+    } else {
+        let redirect = authorize_url(&args.base_url).ok_or(anyhow!("got invalid base url"))?;
+        let (Some(client_id), Some(client_secret)) = (&args.client_id, &args.client_secret) else {
+            return Err(anyhow!("either a music directory or Spotify credentials are required"));
+        };
+        let spotify = AuthCodeSpotify::new(
+            Credentials::new(client_id, client_secret),
+            OAuth {
+                redirect_uri: redirect,
+                scopes: scopes!("user-modify-playback-state"),
+                ..Default::default()
+            }
+        );
+        Arc::new(SpotifyBackend::new(spotify))
+    };
+    let mut rooms = Rooms::new(playback);
+    if let Some(rules) = args.scoring {
+        rooms.rules = rules;
+    }
+    rooms.answer_time = args.answer_time.map(Duration::from_secs);
+    rooms.listening_time = args.listening_time.map(Duration::from_secs);
+    if let Some(window) = args.tie_window {
+        rooms.tie_window = Duration::from_millis(window);
+    }
+    if let Some(path) = &args.name_packs {
+        rooms.names = Arc::new(Names::load(path)?);
+    }
+    if let Some(dir) = &args.state_dir {
+        std::fs::create_dir_all(dir)?;
+        rooms.state_dir = Some(dir.clone());
+    }
+    if args.resume {
+        let dir = args.state_dir.as_ref().ok_or(anyhow!("resuming requires a state directory"))?;
+        for (code, game) in persistence::load_rooms(dir)? {
+            if let Err(e) = rooms.restore(code.clone(), game).await {
+                warn!("Failed to restore room {code}: {e}");
+            }
+        }
+    }
+    let auth = match args.admin_secret {
+        Some(secret) => Auth::new(secret),
+        None => {
+            let auth = Auth::generate();
+            info!("Admin secret: {}", auth.secret());
+            auth
         }
-    );
-    let (channel, _) = channel::<Update>(128);
-    let game = Game {
-        phase: Default::default(),
-        teams: Default::default(),
-        song: Default::default(),
-        channel,
-        spotify,
     };
+    let state = AppState {
+        rooms: Arc::new(rooms),
+        auth: Arc::new(auth),
+    };
+    state.rooms.spawn_cleanup();
     let listener = TcpListener::bind(args.bind).await?;
-    let app = Router::new()
-        .route("/ws/buzzer", any(buzzer_upgrade))
-        .route("/ws/admin", any(admin_upgrade))
-        .route("/ws/spectator", any(spectator_upgrade))
+    let app = app
+        .merge(websocket_router())
         .route("/wasm/client.js", get(wasm_client_js))
         .route("/wasm/client_bg.wasm", get(wasm_client_wasm))
         .route("/style.css", get(wasm_css_wrapper))
         .route("/fonts/{file}", get(fonts))
-        .route("/buzzer", get(wasm_html_wrapper))
-        .route("/admin", get(wasm_html_wrapper))
-        .route("/spectator", get(wasm_html_wrapper))
+        .route("/login", get(wasm_html_wrapper))
+        .route("/admin", get(admin_html_wrapper))
+        .route("/{room}/buzzer", get(wasm_html_wrapper))
+        .route("/{room}/admin", get(admin_html_wrapper))
+        .route("/{room}/spectator", get(wasm_html_wrapper))
         .route("/authorize", get(authorize))
-        .with_state(Arc::new(RwLock::new(game)));
+        .route("/export/results.json", get(results_json))
+        .route("/export/results.csv", get(results_csv))
+        .with_state(state);
     axum::serve(
         listener,
         app.into_make_service_with_connect_info::<SocketAddr>(),
@@ -116,6 +186,17 @@ async fn wasm_html_wrapper() -> impl IntoResponse {
     ([(header::CONTENT_TYPE, "text/html")], INDEX_HTML)
 }
 
+async fn admin_html_wrapper(
+    State(auth): State<Arc<Auth>>,
+    cookie: Option<TypedHeader<Cookie>>,
+) -> axum::response::Response {
+    if auth.verify(&cookie).await {
+        wasm_html_wrapper().await.into_response()
+    } else {
+        Redirect::to("/login").into_response()
+    }
+}
+
 const STYLE_CSS: &'static str = include_str!("../style.css");
 
 async fn wasm_css_wrapper() -> impl IntoResponse {
@@ -220,22 +301,26 @@ struct OauthResponseQuery {
 }
 
 async fn authorize(
-    State(state): State<Arc<RwLock<Game>>>,
+    State(rooms): State<Arc<Rooms>>,
+    State(auth): State<Arc<Auth>>,
+    cookie: Option<TypedHeader<Cookie>>,
     query: Query<OauthResponseQuery>,
 ) -> axum::response::Result<impl IntoResponse> {
-    let game = state.read().await;
+    if !auth.verify(&cookie).await {
+        return Ok(Redirect::temporary("/login"));
+    }
     if let Some(code) = &query.code {
-        game.spotify
-            .request_token(code)
+        rooms.playback
+            .authorize(code)
             .await
-            .map_err(|_| "Got invalid spotify auth code")?;
+            .map_err(|_| "Got invalid auth code")?;
         Ok(Redirect::temporary("/admin"))
     } else {
         Ok(Redirect::temporary(
-            &game
-                .spotify
-                .get_authorize_url(true)
-                .map_err(|_| "Could not get authorize URL")?,
+            &rooms
+                .playback
+                .authorize_url()
+                .ok_or("Could not get authorize URL")?,
         ))
     }
 }
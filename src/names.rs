@@ -1,6 +1,11 @@
-use rand::{seq::SliceRandom, thread_rng};
+use std::{fmt::Display, fs, path::Path};
 
-const ADJECTIVES: &[&'static str] = &[
+use anyhow::anyhow;
+use rand::{thread_rng, Rng};
+use serde::Deserialize;
+use types::message;
+
+const ADJECTIVES: &[&str] = &[
     "Coole",
     "Nice",
     "Mausige",
@@ -39,7 +44,7 @@ const ADJECTIVES: &[&'static str] = &[
     "Geduldige",
 ];
 
-const NOUNS: &[&'static str] = &[
+const NOUNS: &[&str] = &[
     "Mäuse",
     "Tapire",
     "Ameisenbären",
@@ -79,11 +84,256 @@ const NOUNS: &[&'static str] = &[
     "Hammerhaie",
 ];
 
-pub fn random_name() -> String {
-    let mut rng = thread_rng();
-    format!(
-        "{} {}",
-        ADJECTIVES.choose(&mut rng).unwrap(),
-        NOUNS.choose(&mut rng).unwrap()
-    )
+/// How many characters a name proposed by players may have.
+pub const MAX_NAME_LENGTH: usize = 32;
+
+/// Words which may not be part of a name proposed by players.
+const BLOCKED_WORDS: &[&str] = &[
+    "arsch",
+    "arschloch",
+    "asshole",
+    "bitch",
+    "cunt",
+    "fick",
+    "ficken",
+    "fotze",
+    "fuck",
+    "fucker",
+    "hitler",
+    "hure",
+    "nazi",
+    "nazis",
+    "nigger",
+    "penis",
+    "schlampe",
+    "shit",
+    "wichser",
+];
+
+/// Why a name proposed by players is not acceptable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameProblem {
+    /// The name is empty.
+    Empty,
+    /// The name has more than [`MAX_NAME_LENGTH`] characters.
+    TooLong,
+    /// The name contains a blocked word.
+    Blocked,
+}
+
+impl Display for NameProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "name is empty"),
+            Self::TooLong => write!(f, "name is longer than {MAX_NAME_LENGTH} characters"),
+            Self::Blocked => write!(f, "name contains a blocked word"),
+        }
+    }
+}
+
+/// Check a name proposed by players, returning it without
+/// surrounding whitespace if it is acceptable.
+///
+/// Blocked words are matched as whole words, ignoring case
+/// and common digit substitutions such as `3` for `e`.
+pub fn check_name(name: &str) -> Result<String, NameProblem> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(NameProblem::Empty);
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(NameProblem::TooLong);
+    }
+    let normalized: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | '!' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            c => c,
+        })
+        .collect();
+    let blocked = normalized
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| BLOCKED_WORDS.contains(&word));
+    if blocked {
+        return Err(NameProblem::Blocked);
+    }
+    Ok(name.to_string())
+}
+
+/// Whether two names are the same, ignoring case.
+pub fn same_name(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+/// A themed list of words team names are made of.
+///
+/// A name is an adjective followed by a noun, e.g. `Coole Otter`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct NamePack {
+    /// The name of the pack, e.g. its theme.
+    pub name: String,
+    /// The language the names are in, e.g. `de`.
+    pub language: String,
+    pub adjectives: Vec<String>,
+    pub nouns: Vec<String>,
+}
+
+impl NamePack {
+    /// The pack the names of teams are made of by default.
+    fn builtin() -> Self {
+        Self {
+            name: "Tiere".to_string(),
+            language: "de".to_string(),
+            adjectives: ADJECTIVES.iter().map(|word| word.to_string()).collect(),
+            nouns: NOUNS.iter().map(|word| word.to_string()).collect(),
+        }
+    }
+}
+
+/// Every name pack teams can be named from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Names {
+    packs: Vec<NamePack>,
+}
+
+impl Default for Names {
+    fn default() -> Self {
+        Self {
+            packs: vec![NamePack::builtin()],
+        }
+    }
+}
+
+impl Names {
+    /// Load the name packs from the given JSON file, which
+    /// contains a list of packs.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let packs: Vec<NamePack> = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if packs.is_empty() {
+            return Err(anyhow!("no name packs in {}", path.display()));
+        }
+        if let Some(pack) = packs
+            .iter()
+            .find(|pack| pack.adjectives.is_empty() || pack.nouns.is_empty())
+        {
+            return Err(anyhow!("name pack {} has no words", pack.name));
+        }
+        Ok(Self { packs })
+    }
+
+    /// The name and language of every pack.
+    pub fn packs(&self) -> Vec<message::NamePack> {
+        self.packs
+            .iter()
+            .map(|pack| message::NamePack {
+                name: pack.name.clone(),
+                language: pack.language.clone(),
+            })
+            .collect()
+    }
+
+    /// Generate a random name from the pack with the given name,
+    /// or the first one, which none of the given names is.
+    ///
+    /// Once every combination is taken, a number is appended.
+    pub fn generate(&self, pack: Option<&str>, taken: &[String]) -> String {
+        let pack = pack
+            .and_then(|name| self.packs.iter().find(|pack| pack.name == name))
+            .unwrap_or(&self.packs[0]);
+        let is_taken = |name: &str| taken.iter().any(|taken| same_name(taken, name));
+        let name = |index: usize| {
+            let adjective = &pack.adjectives[index / pack.nouns.len()];
+            format!("{adjective} {}", pack.nouns[index % pack.nouns.len()])
+        };
+        // Every combination is visited once in a random order by
+        // stepping through them with a step coprime to their count.
+        let mut rng = thread_rng();
+        let count = pack.adjectives.len() * pack.nouns.len();
+        let step = loop {
+            let step = rng.gen_range(1..=count);
+            if gcd(step, count) == 1 {
+                break step;
+            }
+        };
+        let mut index = rng.gen_range(0..count);
+        for _ in 0..count {
+            let name = name(index);
+            if !is_taken(&name) {
+                return name;
+            }
+            index = (index + step) % count;
+        }
+        let base = name(index);
+        (2..)
+            .map(|n| format!("{base} {n}"))
+            .find(|name| !is_taken(name))
+            .unwrap()
+    }
+}
+
+/// The greatest common divisor of two numbers.
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proposed_names_are_filtered() {
+        assert_eq!(check_name("  Die Otter "), Ok("Die Otter".to_string()));
+        assert_eq!(check_name("   "), Err(NameProblem::Empty));
+        assert_eq!(check_name(&"a".repeat(33)), Err(NameProblem::TooLong));
+        assert_eq!(check_name("Sh1t Happens"), Err(NameProblem::Blocked));
+        assert_eq!(check_name("Scunthorpe United"), Ok("Scunthorpe United".to_string()));
+    }
+
+    #[test]
+    fn generated_names_are_unique() {
+        let names = Names {
+            packs: vec![NamePack {
+                name: "Bands".to_string(),
+                language: "en".to_string(),
+                adjectives: vec!["Rolling".to_string()],
+                nouns: vec!["Tones".to_string(), "Stones".to_string()],
+            }],
+        };
+        let mut taken = Vec::new();
+        for _ in 0..4 {
+            let name = names.generate(Some("Bands"), &taken);
+            assert!(!taken.contains(&name));
+            taken.push(name);
+        }
+        assert!(taken.contains(&"Rolling Tones".to_string()));
+        assert!(taken.contains(&"Rolling Stones".to_string()));
+        assert!(taken.iter().all(|name| name.starts_with("Rolling")));
+        let name = names.generate(None, &["rolling tones".to_string()]);
+        assert_eq!(name, "Rolling Stones");
+
+        let words = |prefix: &str| (0..6).map(|n| format!("{prefix}{n}")).collect::<Vec<_>>();
+        let names = Names {
+            packs: vec![NamePack {
+                name: "Zahlen".to_string(),
+                language: "de".to_string(),
+                adjectives: words("A"),
+                nouns: words("N"),
+            }],
+        };
+        let taken: Vec<String> = (0..36)
+            .map(|n| format!("A{} N{}", n / 6, n % 6))
+            .filter(|name| name != "A4 N1")
+            .collect();
+        assert_eq!(names.generate(None, &taken), "A4 N1");
+    }
 }
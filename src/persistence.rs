@@ -0,0 +1,193 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use log::{debug, error, info};
+use tokio::{
+    sync::{broadcast::error::RecvError, RwLock},
+    task::JoinHandle,
+};
+
+use crate::{game::Game, quiz::Quiz};
+
+/// Read a game from a snapshot file.
+pub fn load(path: &Path) -> anyhow::Result<Game> {
+    let mut game: Game = serde_json::from_str(&fs::read_to_string(path)?)?;
+    game.recover();
+    info!("Restored game from {:?}", path);
+    Ok(game)
+}
+
+/// The snapshot file of the room with the given join code.
+pub fn room_path(dir: &Path, code: &str) -> PathBuf {
+    dir.join(format!("{code}.json"))
+}
+
+/// Read the games of all rooms snapshotted to a directory.
+///
+/// Each game is returned together with the join code
+/// of its room.
+pub fn load_rooms(dir: &Path) -> anyhow::Result<Vec<(String, Game)>> {
+    let mut rooms = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let Some(code) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        rooms.push((code.to_string(), load(&path)?));
+    }
+    Ok(rooms)
+}
+
+/// Write a game to a snapshot file.
+///
+/// The snapshot is written to a temporary file first and then
+/// moved into place, so a crash while writing never leaves
+/// a truncated snapshot behind.
+pub async fn store(path: &Path, game: &Game) -> anyhow::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    tokio::fs::write(&temporary, serde_json::to_string(game)?).await?;
+    tokio::fs::rename(&temporary, path).await?;
+    Ok(())
+}
+
+/// Snapshot the game of a quiz to the given file on every change.
+pub fn spawn_snapshots(state: Arc<RwLock<Quiz>>, path: PathBuf) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut receiver = state.read().await.channel.subscribe();
+        let mut last = None;
+        loop {
+            match receiver.recv().await {
+                Ok(_) | Err(RecvError::Lagged(_)) => (),
+                Err(RecvError::Closed) => break,
+            }
+            let game = state.read().await.game.clone();
+            if last.as_ref() == Some(&game) {
+                continue;
+            }
+            match store(&path, &game).await {
+                Ok(()) => debug!("Stored snapshot in {:?}", path),
+                Err(e) => error!("Failed to store snapshot in {:?}: {e}", path),
+            }
+            last = Some(game);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use types::game::{Phase, Round, Team};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn snapshot_roundtrip() {
+        let path = std::env::temp_dir().join(format!("songquiz-{}.json", std::process::id()));
+        let game = Game {
+            phase: Phase::Guessing {
+                active: vec![0],
+                team: 0,
+                solved: Default::default(),
+                queue: Vec::new(),
+            },
+            teams: vec![
+                Team {
+                    id: 0,
+                    name: "Coole Otter".to_string(),
+                    points: 3,
+                    players: Vec::new(),
+                    proposed: None,
+                },
+                Team {
+                    id: 1,
+                    name: "Fesche Igel".to_string(),
+                    points: 1,
+                    players: Vec::new(),
+                    proposed: None,
+                },
+            ],
+            song: Some(Default::default()),
+            next_team: 2,
+            ..Default::default()
+        };
+        store(&path, &game).await.unwrap();
+        assert_eq!(load(&path).unwrap(), game);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn old_snapshots_get_team_ids() {
+        let path = std::env::temp_dir().join(format!("songquiz-old-{}.json", std::process::id()));
+        let mut game = Game::default();
+        for name in ["Coole Otter", "Fesche Igel"] {
+            game.apply(crate::game::Event::CreateTeam {
+                name: name.to_string(),
+            })
+            .unwrap();
+        }
+        game.phase = Phase::Guessing {
+            active: vec![1],
+            team: 1,
+            solved: Default::default(),
+            queue: Vec::new(),
+        };
+        game.rounds = vec![Round {
+            name: "Schlager".to_string(),
+            songs: 1,
+            points: [(0, 3), (1, 5)].into(),
+        }];
+        let mut json = serde_json::to_value(&game).unwrap();
+        json.as_object_mut().unwrap().remove("next_team");
+        for team in json["teams"].as_array_mut().unwrap() {
+            team.as_object_mut().unwrap().remove("id");
+        }
+        // Old snapshots stored teams by their position.
+        json["phase"]["Guessing"]["active"] = serde_json::json!([false, true]);
+        json["rounds"][0]["points"] = serde_json::json!([3, 5]);
+        fs::write(&path, json.to_string()).unwrap();
+        let mut loaded = load(&path).unwrap();
+        assert_eq!(loaded.teams[1].id, 1);
+        assert_eq!(loaded.phase, game.phase);
+        assert_eq!(loaded.rounds, game.rounds);
+        loaded
+            .apply(crate::game::Event::CreateTeam {
+                name: "Flinke Maus".to_string(),
+            })
+            .unwrap();
+        assert_eq!(loaded.teams[2].id, 2);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn songs_end_on_restore() {
+        let path = std::env::temp_dir().join(format!("songquiz-song-{}.json", std::process::id()));
+        let game = Game {
+            phase: Phase::Listening {
+                active: Vec::new(),
+                solved: Default::default(),
+            },
+            song: Some(Default::default()),
+            ..Default::default()
+        };
+        store(&path, &game).await.unwrap();
+        assert_eq!(load(&path).unwrap().phase, Phase::Selection);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rooms_are_loaded_by_code() {
+        let dir = std::env::temp_dir().join(format!("songquiz-rooms-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        store(&room_path(&dir, "ABCD"), &Game::default()).await.unwrap();
+        fs::write(dir.join("ABCD.json.tmp"), "").unwrap();
+        let rooms = load_rooms(&dir).unwrap();
+        assert_eq!(rooms, vec![("ABCD".to_string(), Game::default())]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,269 @@
+use std::{
+    ffi::OsStr,
+    fs, io,
+    path::{Path as FsPath, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, Request, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use lofty::{file::TaggedFileExt, picture::Picture, tag::Accessor};
+use log::{debug, info};
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+use types::track::{Audio, LibraryEntry, Track};
+
+use super::PlaybackBackend;
+
+const EXTENSIONS: &[&str] = &["mp3", "ogg", "flac"];
+
+/// A single audio file of the local library.
+#[derive(Debug, Clone)]
+struct LocalTrack {
+    path: PathBuf,
+    track: Track,
+    cover: Option<Picture>,
+}
+
+/// All audio files found in a music directory.
+///
+/// Tracks are addressed by ids of the form `local:<n>`
+/// where `n` is the position in the scanned library.
+#[derive(Debug, Default)]
+pub struct Library {
+    tracks: Vec<LocalTrack>,
+}
+
+impl Library {
+    /// Scan the given directory recursively for tagged audio files.
+    pub fn scan(dir: &FsPath) -> io::Result<Self> {
+        let mut paths = Vec::new();
+        collect_files(dir, &mut paths)?;
+        paths.sort();
+        let tracks = paths
+            .into_iter()
+            .filter_map(|path| match read_track(&path) {
+                Ok(track) => Some(track),
+                Err(e) => {
+                    debug!("Skipping {:?}, could not read tags: {e}", path);
+                    None
+                }
+            })
+            .enumerate()
+            .map(|(n, mut track)| {
+                if track.cover.is_some() {
+                    track.track.image = format!("/audio/{n}/cover");
+                }
+                track
+            })
+            .collect::<Vec<_>>();
+        info!("Found {} tracks in {:?}", tracks.len(), dir);
+        Ok(Self { tracks })
+    }
+
+    fn get(&self, id: &str) -> Option<(usize, &LocalTrack)> {
+        let index = id.strip_prefix("local:")?.parse::<usize>().ok()?;
+        Some((index, self.tracks.get(index)?))
+    }
+}
+
+fn collect_files(dir: &FsPath, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, paths)?;
+        } else if path
+            .extension()
+            .and_then(OsStr::to_str)
+            .is_some_and(|ext| EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_track(path: &FsPath) -> anyhow::Result<LocalTrack> {
+    let file = lofty::read_from_path(path)?;
+    let tag = file.primary_tag().or_else(|| file.first_tag());
+    let fallback = path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .to_string();
+    Ok(LocalTrack {
+        path: path.to_path_buf(),
+        track: Track {
+            name: tag
+                .and_then(|tag| tag.title())
+                .map_or(fallback, |title| title.to_string()),
+            artists: tag
+                .and_then(|tag| tag.artist())
+                .map(|artist| vec![artist.to_string()])
+                .unwrap_or_default(),
+            image: String::new(),
+        },
+        cover: tag.and_then(|tag| tag.pictures().first().cloned()),
+    })
+}
+
+/// Playback of a local library in the spectator's browser.
+///
+/// The server does not play anything itself. It only keeps
+/// track of what the spectator page should be playing, which
+/// is sent to it as part of every `SpectatorUpdate`. Every
+/// room keeps track of its own song.
+#[derive(Debug, Clone)]
+pub struct LocalBackend {
+    library: Arc<Library>,
+    audio: Arc<Mutex<Option<Audio>>>,
+}
+
+impl LocalBackend {
+    pub fn new(library: Arc<Library>) -> Self {
+        Self {
+            library,
+            audio: Default::default(),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Audio)) -> anyhow::Result<()> {
+        let mut audio = self.audio.lock().unwrap();
+        let audio = audio.as_mut().ok_or(anyhow!("no track loaded"))?;
+        f(audio);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PlaybackBackend for LocalBackend {
+    fn room(&self) -> anyhow::Result<Arc<dyn PlaybackBackend>> {
+        Ok(Arc::new(Self::new(Arc::clone(&self.library))))
+    }
+
+    async fn track(&self, id: &str) -> anyhow::Result<Track> {
+        let (_, track) = self
+            .library
+            .get(id)
+            .ok_or(anyhow!("no local track with id {id}"))?;
+        Ok(track.track.clone())
+    }
+
+    async fn start(&self, id: &str, position: Duration) -> anyhow::Result<()> {
+        let (index, _) = self
+            .library
+            .get(id)
+            .ok_or(anyhow!("no local track with id {id}"))?;
+        self.audio.lock().unwrap().replace(Audio {
+            url: format!("/audio/{index}"),
+            playing: true,
+            position: position.as_millis() as u64,
+        });
+        Ok(())
+    }
+
+    async fn pause(&self) -> anyhow::Result<()> {
+        self.update(|audio| audio.playing = false)
+    }
+
+    async fn resume(&self) -> anyhow::Result<()> {
+        self.update(|audio| audio.playing = true)
+    }
+
+    async fn seek(&self, position: Duration) -> anyhow::Result<()> {
+        self.update(|audio| audio.position = position.as_millis() as u64)
+    }
+
+    async fn stop(&self) -> anyhow::Result<()> {
+        self.audio.lock().unwrap().take();
+        Ok(())
+    }
+
+    fn library(&self) -> Vec<LibraryEntry> {
+        self.library
+            .tracks
+            .iter()
+            .enumerate()
+            .map(|(n, track)| LibraryEntry {
+                id: format!("local:{n}"),
+                track: track.track.clone(),
+            })
+            .collect()
+    }
+
+    fn audio(&self) -> Option<Audio> {
+        self.audio.lock().unwrap().clone()
+    }
+}
+
+/// The routes serving the audio files and covers of a library.
+pub fn router<S>(library: Arc<Library>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/audio/{track}", get(audio))
+        .route("/audio/{track}/cover", get(cover))
+        .with_state(library)
+}
+
+async fn audio(
+    State(library): State<Arc<Library>>,
+    Path(track): Path<usize>,
+    request: Request,
+) -> Response {
+    if let Some(track) = library.tracks.get(track) {
+        ServeFile::new(&track.path)
+            .oneshot(request)
+            .await
+            .into_response()
+    } else {
+        StatusCode::NOT_FOUND.into_response()
+    }
+}
+
+async fn cover(
+    State(library): State<Arc<Library>>,
+    Path(track): Path<usize>,
+) -> axum::response::Result<impl IntoResponse> {
+    let cover = library
+        .tracks
+        .get(track)
+        .and_then(|track| track.cover.as_ref())
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let mime = cover
+        .mime_type()
+        .map_or("application/octet-stream", |mime| mime.as_str())
+        .to_string();
+    Ok(([(header::CONTENT_TYPE, mime)], cover.data().to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rooms_play_separately() {
+        let library = Library {
+            tracks: vec![LocalTrack {
+                path: PathBuf::from("money.mp3"),
+                track: Default::default(),
+                cover: None,
+            }],
+        };
+        let backend = LocalBackend::new(Arc::new(library));
+        let first = backend.room().unwrap();
+        let second = backend.room().unwrap();
+        first.start("local:0", Duration::ZERO).await.unwrap();
+        assert!(first.audio().is_some());
+        assert!(second.audio().is_none());
+    }
+}
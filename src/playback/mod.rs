@@ -0,0 +1,68 @@
+pub mod local;
+mod spotify;
+
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use types::track::{Audio, LibraryEntry, Track};
+
+pub use local::LocalBackend;
+pub use spotify::SpotifyBackend;
+
+/// A source of songs the game can play from.
+///
+/// The websocket handlers only ever talk to this trait,
+/// so a game can run on Spotify as well as on any other
+/// source implementing it. Tracks are addressed by the
+/// string ids the backend understands, e.g. Spotify URIs.
+#[async_trait]
+pub trait PlaybackBackend: Debug + Send + Sync {
+    /// A backend for a new room, with playback state of its own.
+    ///
+    /// Backends which can only play in one room at a time
+    /// return an error while another room is open.
+    fn room(&self) -> anyhow::Result<Arc<dyn PlaybackBackend>>;
+    /// Look up the metadata of the track with the given id.
+    async fn track(&self, id: &str) -> anyhow::Result<Track>;
+    /// Look up all tracks of the playlist or album with the given id.
+    async fn tracks(&self, id: &str) -> anyhow::Result<Vec<LibraryEntry>> {
+        Err(anyhow!("backend does not support loading {id}"))
+    }
+    /// Start playing the track with the given id from the given position.
+    async fn start(&self, id: &str, position: Duration) -> anyhow::Result<()>;
+    /// Pause the currently playing track.
+    async fn pause(&self) -> anyhow::Result<()>;
+    /// Resume the currently paused track.
+    async fn resume(&self) -> anyhow::Result<()>;
+    /// Jump to the given position in the current track.
+    async fn seek(&self, position: Duration) -> anyhow::Result<()>;
+    /// Stop playback entirely.
+    async fn stop(&self) -> anyhow::Result<()>;
+
+    /// All tracks the admin can choose from directly.
+    ///
+    /// Backends without a fixed library return an empty list.
+    fn library(&self) -> Vec<LibraryEntry> {
+        Vec::new()
+    }
+
+    /// The audio the spectator page should play itself.
+    ///
+    /// Backends playing on an external device return `None`.
+    fn audio(&self) -> Option<Audio> {
+        None
+    }
+
+    /// The URL an admin has to visit to authorize this backend.
+    ///
+    /// Backends which do not need any authorization return `None`.
+    fn authorize_url(&self) -> Option<String> {
+        None
+    }
+
+    /// Finish authorization with the code returned by the provider.
+    async fn authorize(&self, _code: &str) -> anyhow::Result<()> {
+        Err(anyhow!("backend does not support authorization"))
+    }
+}
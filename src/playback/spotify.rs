@@ -0,0 +1,183 @@
+use std::{
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use rspotify::{
+    model::{AlbumId, Image, PlayableItem, PlaylistId, SimplifiedTrack, TrackId},
+    prelude::{BaseClient, Id, OAuthClient},
+    AuthCodeSpotify,
+};
+use types::track::{LibraryEntry, Track};
+
+use super::PlaybackBackend;
+
+/// Playback on the Spotify account authorized via `/authorize`.
+///
+/// Track ids are Spotify track URIs of the form `spotify:track:...`.
+/// The account plays on a single device, so only one room can
+/// be open at a time.
+#[derive(Debug, Clone)]
+pub struct SpotifyBackend {
+    spotify: AuthCodeSpotify,
+    /// The claim of the room which is currently open, if any.
+    open: Arc<Mutex<Weak<()>>>,
+    /// The claim this backend holds on the account, if it
+    /// belongs to a room.
+    _claim: Option<Arc<()>>,
+}
+
+/// How many items to request per page of a playlist or album.
+const PAGE_SIZE: u32 = 50;
+
+impl SpotifyBackend {
+    pub fn new(spotify: AuthCodeSpotify) -> Self {
+        Self {
+            spotify,
+            open: Default::default(),
+            _claim: None,
+        }
+    }
+
+    async fn playlist(&self, id: PlaylistId<'_>) -> anyhow::Result<Vec<LibraryEntry>> {
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = self
+                .spotify
+                .playlist_items_manual(id.clone(), None, None, Some(PAGE_SIZE), Some(offset))
+                .await?;
+            entries.extend(page.items.into_iter().filter_map(|item| match item.track {
+                Some(PlayableItem::Track(track)) => Some(LibraryEntry {
+                    id: track.id.as_ref()?.uri(),
+                    track: track.into(),
+                }),
+                _ => None,
+            }));
+            if page.next.is_none() {
+                break;
+            }
+            offset += PAGE_SIZE;
+        }
+        Ok(entries)
+    }
+
+    async fn album(&self, id: AlbumId<'_>) -> anyhow::Result<Vec<LibraryEntry>> {
+        let album = self.spotify.album(id.clone(), None).await?;
+        let mut tracks = album.tracks.items;
+        let mut next = album.tracks.next;
+        while next.is_some() {
+            let page = self
+                .spotify
+                .album_track_manual(id.clone(), None, Some(PAGE_SIZE), Some(tracks.len() as u32))
+                .await?;
+            tracks.extend(page.items);
+            next = page.next;
+        }
+        Ok(tracks
+            .into_iter()
+            .filter_map(|track| album_entry(track, &album.images))
+            .collect())
+    }
+}
+
+/// Album tracks come without an album, so the cover is passed separately.
+fn album_entry(track: SimplifiedTrack, images: &[Image]) -> Option<LibraryEntry> {
+    Some(LibraryEntry {
+        id: track.id.as_ref()?.uri(),
+        track: Track {
+            name: track.name,
+            artists: track
+                .artists
+                .into_iter()
+                .map(|artist| artist.name)
+                .collect(),
+            image: images
+                .first()
+                .map_or(String::new(), |image| image.url.clone()),
+        },
+    })
+}
+
+fn track_id(id: &str) -> anyhow::Result<TrackId<'_>> {
+    TrackId::from_uri(id).map_err(|e| anyhow!("failed to construct Spotify ID from URI: {e:?}"))
+}
+
+fn time_delta(duration: Duration) -> chrono::Duration {
+    chrono::Duration::milliseconds(duration.as_millis() as i64)
+}
+
+#[async_trait]
+impl PlaybackBackend for SpotifyBackend {
+    fn room(&self) -> anyhow::Result<Arc<dyn PlaybackBackend>> {
+        let mut open = self.open.lock().unwrap();
+        if open.upgrade().is_some() {
+            return Err(anyhow!("Spotify can only play in one room at a time"));
+        }
+        // The claim is released once the room and with it
+        // its backend is dropped.
+        let claim = Arc::new(());
+        *open = Arc::downgrade(&claim);
+        Ok(Arc::new(Self {
+            spotify: self.spotify.clone(),
+            open: Arc::clone(&self.open),
+            _claim: Some(claim),
+        }))
+    }
+
+    async fn track(&self, id: &str) -> anyhow::Result<Track> {
+        Ok(self.spotify.track(track_id(id)?, None).await?.into())
+    }
+
+    async fn tracks(&self, id: &str) -> anyhow::Result<Vec<LibraryEntry>> {
+        if let Ok(playlist) = PlaylistId::from_uri(id) {
+            self.playlist(playlist).await
+        } else if let Ok(album) = AlbumId::from_uri(id) {
+            self.album(album).await
+        } else {
+            Err(anyhow!("{id} is neither a Spotify playlist nor an album"))
+        }
+    }
+
+    async fn start(&self, id: &str, position: Duration) -> anyhow::Result<()> {
+        self.spotify
+            .start_uris_playback(
+                vec![track_id(id)?.into()],
+                None,
+                None,
+                Some(time_delta(position)),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn pause(&self) -> anyhow::Result<()> {
+        self.spotify.pause_playback(None).await?;
+        Ok(())
+    }
+
+    async fn resume(&self) -> anyhow::Result<()> {
+        self.spotify.resume_playback(None, None).await?;
+        Ok(())
+    }
+
+    async fn seek(&self, position: Duration) -> anyhow::Result<()> {
+        self.spotify.seek_track(time_delta(position), None).await?;
+        Ok(())
+    }
+
+    async fn stop(&self) -> anyhow::Result<()> {
+        self.pause().await
+    }
+
+    fn authorize_url(&self) -> Option<String> {
+        self.spotify.get_authorize_url(true).ok()
+    }
+
+    async fn authorize(&self, code: &str) -> anyhow::Result<()> {
+        self.spotify.request_token(code).await?;
+        Ok(())
+    }
+}
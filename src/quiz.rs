@@ -0,0 +1,281 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use log::debug;
+use tokio::{
+    sync::{
+        broadcast::{channel, Sender},
+        mpsc::{unbounded_channel, UnboundedSender},
+        RwLock,
+    },
+    time::sleep,
+};
+
+use types::game::Phase;
+
+use crate::{
+    game::{Effect, Event, Game, Update},
+    playback::PlaybackBackend,
+};
+
+/// Measures how long the current song has been playing.
+///
+/// Pauses, e.g. while a team is guessing, are not counted.
+#[derive(Debug, Clone, Default)]
+pub struct Stopwatch {
+    played: Duration,
+    since: Option<Instant>,
+}
+
+impl Stopwatch {
+    fn start(&mut self) {
+        self.played = Duration::ZERO;
+        self.since = Some(Instant::now());
+    }
+
+    fn pause(&mut self) {
+        if let Some(since) = self.since.take() {
+            self.played += since.elapsed();
+        }
+    }
+
+    fn resume(&mut self) {
+        self.since.get_or_insert_with(Instant::now);
+    }
+
+    /// How long the current song has been playing.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed_at(Instant::now())
+    }
+
+    /// How long the current song had been playing at the given
+    /// instant, e.g. when a buzz arrived.
+    pub fn elapsed_at(&self, instant: Instant) -> Duration {
+        self.played
+            + self
+                .since
+                .map_or(Duration::ZERO, |since| instant.saturating_duration_since(since))
+    }
+}
+
+/// A running quiz.
+///
+/// This ties the pure game state to the communication
+/// channels to update websocket handlers and the playback
+/// backend.
+#[derive(Debug, Clone)]
+pub struct Quiz {
+    /// The state of the game.
+    pub game: Game,
+    /// The channel which is used to communicate a change
+    /// of the game state.
+    pub channel: Sender<Update>,
+    /// The backend used to play songs.
+    pub playback: Arc<dyn PlaybackBackend>,
+    /// How long the current song has been playing.
+    pub stopwatch: Stopwatch,
+    /// When the quiz was created, as a reference for timestamps.
+    created: Instant,
+    /// Where events which are due later are fed back into the quiz.
+    scheduled: UnboundedSender<Event>,
+    /// The turn and end of the current answer timer.
+    deadline: Option<(u64, Instant)>,
+    /// The turn and end of the current listening limit.
+    listening: Option<(u64, Instant)>,
+}
+
+impl Quiz {
+    /// Create a quiz of the given game which plays songs on
+    /// the given backend.
+    ///
+    /// This also spawns the task which dispatches events that are
+    /// due later, such as the end of an answer timer. It stops
+    /// once the quiz is dropped.
+    pub fn spawn(playback: Arc<dyn PlaybackBackend>, game: Game) -> Arc<RwLock<Self>> {
+        let (channel, _) = channel::<Update>(128);
+        let (scheduled, mut due) = unbounded_channel();
+        let quiz = Arc::new(RwLock::new(Self {
+            game,
+            channel,
+            playback,
+            stopwatch: Default::default(),
+            created: Instant::now(),
+            scheduled,
+            deadline: None,
+            listening: None,
+        }));
+        let weak = Arc::downgrade(&quiz);
+        tokio::spawn(async move {
+            while let Some(event) = due.recv().await {
+                let Some(quiz) = weak.upgrade() else {
+                    break;
+                };
+                let mut quiz = quiz.write().await;
+                if let Err(e) = quiz.dispatch(event).await {
+                    debug!("Ignoring scheduled event: {e}");
+                }
+            }
+        });
+        quiz
+    }
+
+    /// Close the quiz by pausing its song and ending every
+    /// connection to it.
+    ///
+    /// Its own playback is dropped in favour of the given one,
+    /// so the backend can play in another room right away, even
+    /// before the last connection has ended.
+    pub async fn close(&mut self, playback: Arc<dyn PlaybackBackend>) {
+        if self.game.playing {
+            if let Err(e) = self.playback.pause().await {
+                debug!("Playback backend returned error {e}, likely not authorized");
+            }
+        }
+        self.playback = playback;
+        self.stopwatch.pause();
+        // Handlers exit once the channel they listen on is closed.
+        self.channel = channel(1).0;
+    }
+
+    /// Start the timers of the current turn of a restored game.
+    pub async fn restart_timers(&mut self) {
+        for effect in self.game.restart_timers() {
+            if let Err(e) = self.execute(effect).await {
+                debug!("Playback backend returned error {e}, likely not authorized");
+            }
+        }
+    }
+
+    /// How much time the guessing team has left to answer,
+    /// if there is a limit.
+    pub fn time_left(&self) -> Option<Duration> {
+        let (turn, deadline) = self.deadline?;
+        let Phase::Guessing { .. } = self.game.phase else {
+            return None;
+        };
+        (turn == self.game.turn).then(|| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// How much longer the song may be listened to before it is
+    /// revealed, if there is a limit.
+    pub fn listening_left(&self) -> Option<Duration> {
+        let (turn, end) = self.listening?;
+        let Phase::Listening { .. } = self.game.phase else {
+            return None;
+        };
+        (turn == self.game.turn).then(|| end.saturating_duration_since(Instant::now()))
+    }
+
+    /// The time of the given instant on the clock buzzes
+    /// are timestamped with.
+    pub fn timestamp(&self, instant: Instant) -> Duration {
+        instant.saturating_duration_since(self.created)
+    }
+
+    /// Apply an event to the game and carry out its effects.
+    ///
+    /// If a song cannot be started, the game is rolled back,
+    /// so it never claims to play a song which does not play.
+    pub async fn dispatch(&mut self, event: Event) -> anyhow::Result<()> {
+        let before = self.game.clone();
+        for effect in self.game.apply(event)? {
+            let play = matches!(effect, Effect::Play { .. });
+            if let Err(e) = self.execute(effect).await {
+                if play {
+                    self.game = before;
+                    self.stopwatch.pause();
+                    return Err(e.context("failed to start playback"));
+                }
+                debug!("Playback backend returned error {e}, likely not authorized");
+            }
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self, effect: Effect) -> anyhow::Result<()> {
+        match effect {
+            Effect::Play { id, position } => {
+                self.stopwatch.start();
+                self.playback.start(&id, position).await
+            }
+            Effect::Pause => {
+                self.stopwatch.pause();
+                self.playback.pause().await
+            }
+            Effect::Resume => {
+                self.stopwatch.resume();
+                self.playback.resume().await
+            }
+            Effect::Broadcast(update) => {
+                let _ = self.channel.send(update);
+                Ok(())
+            }
+            Effect::StartTimer { turn, time } => {
+                self.deadline = Some((turn, Instant::now() + time));
+                let scheduled = self.scheduled.clone();
+                tokio::spawn(async move {
+                    sleep(time).await;
+                    let _ = scheduled.send(Event::TimeUp { turn });
+                });
+                Ok(())
+            }
+            Effect::EndSnippet { turn, time } => {
+                let left = time.saturating_sub(self.stopwatch.elapsed());
+                let scheduled = self.scheduled.clone();
+                tokio::spawn(async move {
+                    sleep(left).await;
+                    let _ = scheduled.send(Event::SnippetOver { turn });
+                });
+                Ok(())
+            }
+            Effect::LimitListening { turn, time } => {
+                // The limit refers to the total playing time, which
+                // does not include pauses for guesses.
+                let left = time.saturating_sub(self.stopwatch.elapsed());
+                self.listening = Some((turn, Instant::now() + left));
+                let scheduled = self.scheduled.clone();
+                tokio::spawn(async move {
+                    sleep(left).await;
+                    let _ = scheduled.send(Event::ListeningTimeUp { turn });
+                });
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playback::LocalBackend;
+
+    #[tokio::test]
+    async fn failed_playback_keeps_selection() {
+        // The library is empty, so no track can be started.
+        let quiz = Quiz::spawn(Arc::new(LocalBackend::new(Default::default())), Game::default());
+        let mut quiz = quiz.write().await;
+        let before = quiz.game.clone();
+        let event = Event::Select {
+            id: "local:0".to_string(),
+            track: Default::default(),
+            start: Duration::ZERO,
+            snippet: None,
+            at: 0,
+        };
+        assert!(quiz.dispatch(event).await.is_err());
+        assert_eq!(quiz.game, before);
+        assert_eq!(quiz.game.phase, Phase::Selection);
+    }
+
+    #[test]
+    fn stopwatch_measures_until_arrival() {
+        let mut stopwatch = Stopwatch::default();
+        stopwatch.start();
+        let since = stopwatch.since.unwrap();
+        assert_eq!(stopwatch.elapsed_at(since + Duration::from_millis(300)), Duration::from_millis(300));
+        assert_eq!(stopwatch.elapsed_at(since - Duration::from_millis(1)), Duration::ZERO);
+        assert!(stopwatch.elapsed() >= stopwatch.elapsed_at(since));
+    }
+}
@@ -0,0 +1,335 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use axum_extra::{headers::Cookie, TypedHeader};
+use log::{debug, info};
+use rand::{seq::SliceRandom, thread_rng};
+use tokio::{
+    sync::RwLock,
+    task::JoinHandle,
+    time::{interval, MissedTickBehavior},
+};
+use types::scoring::ScoringRules;
+
+use crate::{
+    auth::Auth, game::Game, names::Names, persistence, playback::PlaybackBackend, quiz::Quiz,
+};
+
+/// The characters a join code is made of.
+///
+/// Characters which are easily confused with each
+/// other, such as `0` and `O`, are left out.
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// How many characters a join code has.
+const CODE_LENGTH: usize = 4;
+
+/// How long a room may go without any connection
+/// before it is removed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// How close buzzes have to be to be ordered by compensated
+/// time, unless configured otherwise.
+pub const DEFAULT_TIE_WINDOW: Duration = Duration::from_millis(150);
+
+/// How often rooms are checked for idleness.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A single game room.
+#[derive(Debug)]
+struct Room {
+    quiz: Arc<RwLock<Quiz>>,
+    /// The task writing snapshots of this room, if any.
+    snapshots: Option<JoinHandle<()>>,
+    /// Since when nobody is connected to this room.
+    idle_since: Option<Instant>,
+}
+
+/// The registry of all rooms on this server.
+///
+/// Every room runs its own quiz and is addressed by
+/// a short join code. Each room plays its songs on its
+/// own instance of the playback backend.
+#[derive(Debug)]
+pub struct Rooms {
+    rooms: RwLock<HashMap<String, Room>>,
+    /// The backend the playback of every room is created from.
+    pub playback: Arc<dyn PlaybackBackend>,
+    /// How guesses are scored in newly created rooms.
+    pub rules: ScoringRules,
+    /// How long teams may take to answer in newly created rooms.
+    pub answer_time: Option<Duration>,
+    /// How long songs may be listened to in newly created rooms.
+    pub listening_time: Option<Duration>,
+    /// How close buzzes have to be in newly created rooms to
+    /// be ordered by compensated time.
+    pub tie_window: Duration,
+    /// The directory each room is snapshotted to, if any.
+    pub state_dir: Option<PathBuf>,
+    /// The name packs teams in every room are named from.
+    pub names: Arc<Names>,
+}
+
+impl Rooms {
+    /// Create an empty registry whose rooms play songs on
+    /// the given backend.
+    pub fn new(playback: Arc<dyn PlaybackBackend>) -> Self {
+        Self {
+            rooms: Default::default(),
+            playback,
+            rules: Default::default(),
+            answer_time: None,
+            listening_time: None,
+            tie_window: DEFAULT_TIE_WINDOW,
+            state_dir: None,
+            names: Default::default(),
+        }
+    }
+
+    /// Create a room with a fresh game and return its join code.
+    ///
+    /// This fails if the backend cannot play in another room.
+    pub async fn create(&self) -> anyhow::Result<String> {
+        let mut rooms = self.rooms.write().await;
+        let code = loop {
+            let code = random_code();
+            if !rooms.contains_key(&code) {
+                break code;
+            }
+        };
+        let game = Game {
+            rules: self.rules.clone(),
+            answer_time: self.answer_time,
+            listening_time: self.listening_time,
+            tie_window: self.tie_window,
+            ..Default::default()
+        };
+        rooms.insert(code.clone(), self.open(&code, game)?);
+        info!("Room {code} created");
+        Ok(code)
+    }
+
+    /// Restore a room with the given join code and game.
+    ///
+    /// Timers of the current turn start over, as they did
+    /// not survive the restart.
+    pub async fn restore(&self, code: String, game: Game) -> anyhow::Result<()> {
+        let room = self.open(&code, game)?;
+        room.quiz.write().await.restart_timers().await;
+        self.rooms.write().await.insert(code, room);
+        Ok(())
+    }
+
+    fn open(&self, code: &str, game: Game) -> anyhow::Result<Room> {
+        let quiz = Quiz::spawn(self.playback.room()?, game);
+        let snapshots = self.state_dir.as_ref().map(|dir| {
+            persistence::spawn_snapshots(Arc::clone(&quiz), persistence::room_path(dir, code))
+        });
+        Ok(Room {
+            quiz,
+            snapshots,
+            idle_since: None,
+        })
+    }
+
+    /// The quiz of the room with the given join code.
+    pub async fn get(&self, code: &str) -> Option<Arc<RwLock<Quiz>>> {
+        let rooms = self.rooms.read().await;
+        rooms.get(&code.to_uppercase()).map(|room| Arc::clone(&room.quiz))
+    }
+
+    /// Remove every room nobody has been connected to
+    /// for longer than the idle timeout.
+    pub async fn sweep(&self) {
+        // The quizzes are locked one after another without
+        // holding up the registry.
+        let quizzes: Vec<(String, Arc<RwLock<Quiz>>)> = {
+            let rooms = self.rooms.read().await;
+            rooms
+                .iter()
+                .map(|(code, room)| (code.clone(), Arc::clone(&room.quiz)))
+                .collect()
+        };
+        let mut subscribers = Vec::new();
+        for (code, quiz) in quizzes {
+            subscribers.push((code, quiz.read().await.channel.receiver_count()));
+        }
+        let mut removed = Vec::new();
+        {
+            let mut rooms = self.rooms.write().await;
+            for (code, subscribers) in subscribers {
+                let Some(room) = rooms.get_mut(&code) else {
+                    continue;
+                };
+                // The snapshot task is subscribed as well, but
+                // is no connection.
+                if subscribers > room.snapshots.is_some() as usize {
+                    room.idle_since = None;
+                    continue;
+                }
+                let since = *room.idle_since.get_or_insert_with(Instant::now);
+                if since.elapsed() < IDLE_TIMEOUT {
+                    continue;
+                }
+                if let Some(room) = rooms.remove(&code) {
+                    if let Some(snapshots) = room.snapshots {
+                        snapshots.abort();
+                    }
+                    removed.push(code);
+                }
+            }
+        }
+        for code in removed {
+            self.remove_snapshot(&code).await;
+            info!("Room {code} removed after being idle");
+        }
+    }
+
+    /// Close the room with the given join code right away.
+    ///
+    /// Every connection to it ends, and its playback is paused
+    /// and released. Returns whether there was such a room.
+    pub async fn close(&self, code: &str) -> bool {
+        let code = code.to_uppercase();
+        let Some(room) = self.rooms.write().await.remove(&code) else {
+            return false;
+        };
+        if let Some(snapshots) = room.snapshots {
+            snapshots.abort();
+        }
+        room.quiz
+            .write()
+            .await
+            .close(Arc::clone(&self.playback))
+            .await;
+        self.remove_snapshot(&code).await;
+        info!("Room {code} closed");
+        true
+    }
+
+    async fn remove_snapshot(&self, code: &str) {
+        if let Some(dir) = &self.state_dir {
+            if let Err(e) = tokio::fs::remove_file(persistence::room_path(dir, code)).await {
+                debug!("Failed to remove snapshot of room {code}: {e}");
+            }
+        }
+    }
+
+    /// Periodically remove idle rooms.
+    pub fn spawn_cleanup(self: &Arc<Self>) -> JoinHandle<()> {
+        let rooms = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticks = interval(SWEEP_INTERVAL);
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                rooms.sweep().await;
+            }
+        })
+    }
+}
+
+fn random_code() -> String {
+    let mut rng = thread_rng();
+    (0..CODE_LENGTH)
+        .map(|_| *CODE_ALPHABET.choose(&mut rng).unwrap() as char)
+        .collect()
+}
+
+/// Create a new room and respond with its join code.
+///
+/// Only a logged in admin may create rooms.
+pub async fn create_room(
+    State(rooms): State<Arc<Rooms>>,
+    State(auth): State<Arc<Auth>>,
+    cookie: Option<TypedHeader<Cookie>>,
+) -> Result<String, StatusCode> {
+    if !auth.verify(&cookie).await {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    rooms.create().await.map_err(|e| {
+        info!("Refusing to create a room: {e}");
+        StatusCode::CONFLICT
+    })
+}
+
+/// Close a room, ending every connection to it.
+///
+/// Only a logged in admin may close rooms.
+pub async fn close_room(
+    State(rooms): State<Arc<Rooms>>,
+    State(auth): State<Arc<Auth>>,
+    Path(room): Path<String>,
+    cookie: Option<TypedHeader<Cookie>>,
+) -> StatusCode {
+    if !auth.verify(&cookie).await {
+        return StatusCode::UNAUTHORIZED;
+    }
+    if rooms.close(&room).await {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::broadcast::error::RecvError;
+    use types::game::Team;
+
+    use super::*;
+    use crate::playback::LocalBackend;
+
+    fn rooms() -> Rooms {
+        Rooms::new(Arc::new(LocalBackend::new(Default::default())))
+    }
+
+    #[tokio::test]
+    async fn rooms_are_separate() {
+        let rooms = rooms();
+        let first = rooms.create().await.unwrap();
+        let second = rooms.create().await.unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first.len(), CODE_LENGTH);
+        rooms.get(&first).await.unwrap().write().await.game.teams.push(Team {
+            id: 0,
+            name: "Coole Otter".to_string(),
+            points: 0,
+            players: Vec::new(),
+            proposed: None,
+        });
+        assert_eq!(rooms.get(&first).await.unwrap().read().await.game.teams.len(), 1);
+        assert!(rooms.get(&second).await.unwrap().read().await.game.teams.is_empty());
+        assert!(rooms.get(&first.to_lowercase()).await.is_some());
+        assert!(rooms.get("----").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn connected_rooms_are_kept() {
+        let rooms = rooms();
+        let code = rooms.create().await.unwrap();
+        let _receiver = rooms.get(&code).await.unwrap().read().await.channel.subscribe();
+        rooms.sweep().await;
+        assert!(rooms.rooms.read().await[&code].idle_since.is_none());
+        assert!(rooms.get(&code).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn closed_rooms_end_connections() {
+        let rooms = rooms();
+        let code = rooms.create().await.unwrap();
+        let mut receiver = rooms.get(&code).await.unwrap().read().await.channel.subscribe();
+        assert!(rooms.close(&code.to_lowercase()).await);
+        assert!(rooms.get(&code).await.is_none());
+        assert!(matches!(receiver.recv().await, Err(RecvError::Closed)));
+        assert!(!rooms.close(&code).await);
+    }
+}
@@ -3,46 +3,94 @@ use std::{net::SocketAddr, sync::Arc};
 use axum::{
     extract::{
         ws::{Message, WebSocket},
-        ConnectInfo, State, WebSocketUpgrade,
+        ConnectInfo, Path, State, WebSocketUpgrade,
     },
-    response::IntoResponse,
+    http::StatusCode,
+    response::{IntoResponse, Response},
 };
 use axum_extra::{headers::UserAgent, TypedHeader};
 use log::debug;
 use tokio::sync::RwLock;
-use types::{game::Phase, message::SpectatorUpdate};
+use types::{
+    game::{Phase, Solved, Team},
+    message::SpectatorUpdate,
+    track::Track,
+};
+
+use crate::{quiz::Quiz, room::Rooms};
 
-use crate::game::Game;
+/// How many previously played songs spectators are shown.
+const PREVIOUS_SONGS: usize = 10;
 
 pub async fn spectator_upgrade(
-    State(state): State<Arc<RwLock<Game>>>,
+    State(rooms): State<Arc<Rooms>>,
+    Path(room): Path<String>,
     ws: WebSocketUpgrade,
     user_agent: Option<TypedHeader<UserAgent>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-) -> impl IntoResponse {
+) -> Response {
+    let Some(state) = rooms.get(&room).await else {
+        debug!("Rejecting spectator connection from {:?} to unknown room {room}", addr);
+        return StatusCode::NOT_FOUND.into_response();
+    };
     debug!(
-        "Got buzzer connection from {:?} at {:?}, entering handler",
+        "Got spectator connection from {:?} at {:?} for room {room}, entering handler",
         user_agent, addr
     );
     ws.on_upgrade(move |socket| spectator_handler(state, socket, addr))
+        .into_response()
 }
 
-async fn spectator_handler(state: Arc<RwLock<Game>>, mut socket: WebSocket, addr: SocketAddr) {
+async fn spectator_handler(state: Arc<RwLock<Quiz>>, mut socket: WebSocket, addr: SocketAddr) {
     let mut receiver = {
-        let game = state.read().await;
-        let receiver = game.channel.subscribe();
-        let _ = game.channel.send(Default::default());
+        let quiz = state.read().await;
+        let receiver = quiz.channel.subscribe();
+        let _ = quiz.channel.send(Default::default());
         receiver
     };
     while let Ok(_) = receiver.recv().await {
         let update = {
-            let game = state.read().await;
+            let quiz = state.read().await;
             SpectatorUpdate {
-                phase: game.phase.clone(),
-                teams: game.teams.clone(),
-                revealed: match &game.phase {
-                    Phase::Selection => game.song.clone().map(|t| t.into()),
-                    _ => None,
+                phase: quiz.game.phase.clone(),
+                // Proposed names are not shown before they are approved.
+                teams: quiz
+                    .game
+                    .teams
+                    .iter()
+                    .map(|team| Team {
+                        proposed: None,
+                        ..team.clone()
+                    })
+                    .collect(),
+                revealed: match &quiz.game.phase {
+                    Phase::Selection => quiz.game.song.clone(),
+                    Phase::Listening { solved, .. } | Phase::Guessing { solved, .. } => {
+                        quiz.game.song.as_ref().and_then(|song| partial(song, solved))
+                    }
+                    Phase::RoundOver { .. } | Phase::Finished => None,
+                },
+                audio: quiz.playback.audio(),
+                buzzes: quiz.game.buzzes(),
+                time_left: quiz.time_left().map(|time| time.as_millis() as u64),
+                listening_time: quiz.game.listening_time.map(|time| time.as_millis() as u64),
+                listening_left: quiz.listening_left().map(|time| time.as_millis() as u64),
+                rounds: quiz.game.rounds.clone(),
+                round: quiz.game.round,
+                previous: {
+                    // The current song is only listed once it is over.
+                    let current = match quiz.game.phase {
+                        Phase::Listening { .. } | Phase::Guessing { .. } => 1,
+                        _ => 0,
+                    };
+                    quiz.game
+                        .log
+                        .iter()
+                        .rev()
+                        .skip(current)
+                        .take(PREVIOUS_SONGS)
+                        .map(|song| song.track.clone())
+                        .collect()
                 },
             }
         };
@@ -56,3 +104,23 @@ async fn spectator_handler(state: Arc<RwLock<Game>>, mut socket: WebSocket, addr
     }
     debug!("Internal channel closed, exiting handler");
 }
+
+/// The parts of a song which have already been solved.
+fn partial(song: &Track, solved: &Solved) -> Option<Track> {
+    if !solved.title && !solved.artist {
+        return None;
+    }
+    Some(Track {
+        name: if solved.title {
+            song.name.clone()
+        } else {
+            String::new()
+        },
+        artists: if solved.artist {
+            song.artists.clone()
+        } else {
+            Vec::new()
+        },
+        image: String::new(),
+    })
+}
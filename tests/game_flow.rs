@@ -0,0 +1,287 @@
+mod spotify_mock;
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use futures::{SinkExt, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use songquiz::{
+    auth::{Auth, SESSION_COOKIE},
+    playback::SpotifyBackend,
+    room::Rooms,
+    websocket_router, AppState,
+};
+use spotify_mock::{Call, SpotifyMock, TRACK_ID};
+use tokio::{net::TcpListener, time::timeout};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{client::IntoClientRequest, http::header, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+use types::{
+    game::Phase,
+    message::{
+        AdminInteraction, AdminLog, AdminUpdate, BuzzerInteraction, BuzzerUpdate,
+        SpectatorUpdate,
+    },
+};
+
+type Socket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// A running server with a single room.
+struct Server {
+    addr: SocketAddr,
+    /// The join code of the room.
+    room: String,
+    /// The session token of a logged in admin.
+    session: String,
+}
+
+/// Serve a single fresh room backed by the mock.
+async fn serve(mock: &SpotifyMock) -> Server {
+    let playback = SpotifyBackend::new(mock.client());
+    let rooms = Rooms::new(Arc::new(playback));
+    rooms.playback.authorize("mock-code").await.unwrap();
+    let room = rooms.create().await.unwrap();
+    let auth = Auth::new("geheim".to_string());
+    let session = auth.login("geheim").await.unwrap();
+    let app = websocket_router().with_state(AppState {
+        rooms: Arc::new(rooms),
+        auth: Arc::new(auth),
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap()
+    });
+    Server {
+        addr,
+        room,
+        session,
+    }
+}
+
+async fn connect(server: &Server, endpoint: &str) -> Socket {
+    let mut request = format!("ws://{}/ws/{}/{endpoint}", server.addr, server.room)
+        .into_client_request()
+        .unwrap();
+    request.headers_mut().insert(
+        header::COOKIE,
+        format!("{SESSION_COOKIE}={}", server.session).parse().unwrap(),
+    );
+    let (socket, _) = connect_async(request).await.unwrap();
+    socket
+}
+
+async fn send<T: Serialize>(socket: &mut Socket, interaction: &T) {
+    socket
+        .send(Message::text(serde_json::to_string(interaction).unwrap()))
+        .await
+        .unwrap();
+}
+
+/// Read updates until one satisfies the predicate.
+///
+/// Every connection and interaction broadcasts to all handlers,
+/// so intermediate updates are skipped rather than asserted.
+async fn expect<T, F>(socket: &mut Socket, predicate: F) -> T
+where
+    T: DeserializeOwned,
+    F: Fn(&T) -> bool,
+{
+    timeout(Duration::from_secs(5), async {
+        loop {
+            let message = socket.next().await.unwrap().unwrap();
+            let Message::Text(text) = message else {
+                continue;
+            };
+            // Admins also receive the game log, which is skipped here.
+            if serde_json::from_str::<AdminLog>(&text).is_ok() {
+                continue;
+            }
+            let update = serde_json::from_str::<T>(&text).unwrap();
+            if predicate(&update) {
+                return update;
+            }
+        }
+    })
+    .await
+    .expect("no matching update received")
+}
+
+/// Connect all three clients and register the buzzer for a fresh team.
+async fn setup(server: &Server) -> (Socket, Socket, Socket) {
+    let mut admin = connect(server, "admin").await;
+    expect(&mut admin, |update: &AdminUpdate| {
+        update.phase == Phase::Selection
+    })
+    .await;
+    send(&mut admin, &AdminInteraction::CreateTeam).await;
+    expect(&mut admin, |update: &AdminUpdate| update.teams.len() == 1).await;
+
+    let mut buzzer = connect(server, "buzzer").await;
+    send(&mut buzzer, &BuzzerInteraction::Register { team: 0, player: None }).await;
+    expect(&mut buzzer, |update: &BuzzerUpdate| {
+        update.registered == Some(0)
+    })
+    .await;
+
+    let mut spectator = connect(server, "spectator").await;
+    expect(&mut spectator, |update: &SpectatorUpdate| {
+        update.teams.len() == 1
+    })
+    .await;
+    (admin, buzzer, spectator)
+}
+
+async fn select_track(admin: &mut Socket, buzzer: &mut Socket, spectator: &mut Socket) {
+    send(
+        admin,
+        &AdminInteraction::Selection {
+            id: format!("spotify:track:{TRACK_ID}"),
+            start: None,
+            duration: None,
+        },
+    )
+    .await;
+    let update = expect(admin, |update: &AdminUpdate| {
+        matches!(update.phase, Phase::Listening { .. })
+    })
+    .await;
+    assert_eq!(update.song.unwrap().name, "Money");
+    let update = expect(buzzer, |update: &BuzzerUpdate| {
+        matches!(update.phase, Phase::Listening { .. })
+    })
+    .await;
+    assert!(update.phase.is_active(0));
+    let update = expect(spectator, |update: &SpectatorUpdate| {
+        matches!(update.phase, Phase::Listening { .. })
+    })
+    .await;
+    assert_eq!(update.revealed, None);
+}
+
+async fn buzz(admin: &mut Socket, buzzer: &mut Socket, spectator: &mut Socket) {
+    send(buzzer, &BuzzerInteraction::Buzz).await;
+    let guessing = |phase: &Phase| matches!(phase, Phase::Guessing { team: 0, .. });
+    expect(buzzer, |update: &BuzzerUpdate| guessing(&update.phase)).await;
+    expect(spectator, |update: &SpectatorUpdate| guessing(&update.phase)).await;
+    expect(admin, |update: &AdminUpdate| guessing(&update.phase)).await;
+}
+
+#[tokio::test]
+async fn accepted_guess_scores_and_reveals() {
+    let mock = SpotifyMock::start().await;
+    let server = serve(&mock).await;
+    let (mut admin, mut buzzer, mut spectator) = setup(&server).await;
+
+    select_track(&mut admin, &mut buzzer, &mut spectator).await;
+    buzz(&mut admin, &mut buzzer, &mut spectator).await;
+
+    send(&mut admin, &AdminInteraction::AcceptGuess).await;
+    let update = expect(&mut admin, |update: &AdminUpdate| {
+        update.phase == Phase::Selection
+    })
+    .await;
+    assert_eq!(update.teams[0].points, 1);
+    let update = expect(&mut spectator, |update: &SpectatorUpdate| {
+        update.phase == Phase::Selection
+    })
+    .await;
+    assert_eq!(update.teams[0].points, 1);
+    assert_eq!(update.revealed.unwrap().artists, vec!["Pink Floyd"]);
+    expect(&mut buzzer, |update: &BuzzerUpdate| {
+        update.phase == Phase::Selection && update.teams[0].points == 1
+    })
+    .await;
+
+    assert_eq!(
+        mock.calls(),
+        vec![
+            Call::Token,
+            Call::Play(vec![format!("spotify:track:{TRACK_ID}")]),
+            Call::Pause,
+            Call::Resume,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn rejected_guess_of_last_team_ends_round() {
+    let mock = SpotifyMock::start().await;
+    let server = serve(&mock).await;
+    let (mut admin, mut buzzer, mut spectator) = setup(&server).await;
+
+    select_track(&mut admin, &mut buzzer, &mut spectator).await;
+    buzz(&mut admin, &mut buzzer, &mut spectator).await;
+
+    send(&mut admin, &AdminInteraction::RejectGuess).await;
+    let update = expect(&mut admin, |update: &AdminUpdate| {
+        update.phase == Phase::Selection
+    })
+    .await;
+    assert_eq!(update.teams[0].points, 0);
+    let update = expect(&mut spectator, |update: &SpectatorUpdate| {
+        update.phase == Phase::Selection
+    })
+    .await;
+    assert_eq!(update.revealed.unwrap().name, "Money");
+}
+
+#[tokio::test]
+async fn unknown_track_keeps_selection() {
+    let mock = SpotifyMock::start().await;
+    let server = serve(&mock).await;
+    let (mut admin, _buzzer, _spectator) = setup(&server).await;
+
+    send(
+        &mut admin,
+        &AdminInteraction::Selection {
+            id: "spotify:track:0000000000000000000000".to_string(),
+            start: None,
+            duration: None,
+        },
+    )
+    .await;
+    send(&mut admin, &AdminInteraction::CreateTeam).await;
+    let update = expect(&mut admin, |update: &AdminUpdate| update.teams.len() == 2).await;
+    assert_eq!(update.phase, Phase::Selection);
+    assert_eq!(mock.calls(), vec![Call::Token]);
+}
+
+#[tokio::test]
+async fn unknown_room_is_rejected() {
+    let mock = SpotifyMock::start().await;
+    let server = serve(&mock).await;
+    assert!(connect_async(format!("ws://{}/ws/0000/spectator", server.addr))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn admin_requires_login() {
+    let mock = SpotifyMock::start().await;
+    let server = serve(&mock).await;
+    assert!(
+        connect_async(format!("ws://{}/ws/{}/admin", server.addr, server.room))
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn spotify_plays_in_one_room() {
+    let mock = SpotifyMock::start().await;
+    let rooms = Rooms::new(Arc::new(SpotifyBackend::new(mock.client())));
+    let code = rooms.create().await.unwrap();
+    assert!(rooms.create().await.is_err());
+    // Closing the room releases the account even while a
+    // connection to it is still open.
+    let _quiz = rooms.get(&code).await.unwrap();
+    assert!(rooms.close(&code).await);
+    rooms.create().await.unwrap();
+}
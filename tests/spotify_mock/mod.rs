@@ -0,0 +1,163 @@
+//! A local stand-in for the parts of the Spotify Web API songquiz uses.
+//!
+//! Every request the mock receives is recorded as a [`Call`], so tests
+//! can assert what the playback backend asked Spotify to do.
+
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post, put},
+    Json, Router,
+};
+use rspotify::{AuthCodeSpotify, Config, Credentials, OAuth};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+
+/// The id of the only track the mock knows about.
+pub const TRACK_ID: &str = "4cOdK2wGLETKBW3PvgPWqT";
+
+/// A request the mock has received.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Call {
+    /// An authorization code was exchanged for a token.
+    Token,
+    /// Playback of the given URIs was started.
+    Play(Vec<String>),
+    /// Playback was paused.
+    Pause,
+    /// Playback was resumed.
+    Resume,
+}
+
+/// A running mock server.
+#[derive(Debug, Clone)]
+pub struct SpotifyMock {
+    addr: SocketAddr,
+    calls: Arc<Mutex<Vec<Call>>>,
+}
+
+impl SpotifyMock {
+    /// Start the mock on a random local port.
+    pub async fn start() -> Self {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route("/api/token", post(token))
+            .route("/v1/tracks/{id}", get(track))
+            .route("/v1/me/player/play", put(play))
+            .route("/v1/me/player/pause", put(pause))
+            .with_state(Arc::clone(&calls));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        Self { addr, calls }
+    }
+
+    /// A Spotify client which talks to this mock instead of Spotify.
+    pub fn client(&self) -> AuthCodeSpotify {
+        AuthCodeSpotify::with_config(
+            Credentials::new("client-id", "client-secret"),
+            OAuth {
+                redirect_uri: "http://localhost/authorize".to_string(),
+                ..Default::default()
+            },
+            Config {
+                api_base_url: format!("http://{}/v1/", self.addr),
+                auth_base_url: format!("http://{}/", self.addr),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// All requests received so far, in order.
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+type Calls = State<Arc<Mutex<Vec<Call>>>>;
+
+async fn token(State(calls): Calls) -> impl IntoResponse {
+    calls.lock().unwrap().push(Call::Token);
+    Json(json!({
+        "access_token": "mock-access-token",
+        "token_type": "Bearer",
+        "scope": "user-modify-playback-state",
+        "expires_in": 3600,
+        "refresh_token": "mock-refresh-token",
+    }))
+}
+
+async fn track(Path(id): Path<String>) -> Result<impl IntoResponse, StatusCode> {
+    if id != TRACK_ID {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Json(json!({
+        "album": {
+            "album_type": "album",
+            "artists": [artist()],
+            "available_markets": ["DE"],
+            "external_urls": { "spotify": "https://open.spotify.com/album/6dVIqQ8qmQ5GBnJ9shOYGE" },
+            "href": "https://api.spotify.com/v1/albums/6dVIqQ8qmQ5GBnJ9shOYGE",
+            "id": "6dVIqQ8qmQ5GBnJ9shOYGE",
+            "images": [{ "height": 640, "width": 640, "url": "https://i.scdn.co/image/cover" }],
+            "name": "The Dark Side of the Moon",
+            "release_date": "1973-03-01",
+            "release_date_precision": "day",
+            "total_tracks": 10,
+            "type": "album",
+            "uri": "spotify:album:6dVIqQ8qmQ5GBnJ9shOYGE"
+        },
+        "artists": [artist()],
+        "available_markets": ["DE"],
+        "disc_number": 1,
+        "duration_ms": 382296,
+        "explicit": false,
+        "external_ids": { "isrc": "GBN9Y1100088" },
+        "external_urls": { "spotify": format!("https://open.spotify.com/track/{TRACK_ID}") },
+        "href": format!("https://api.spotify.com/v1/tracks/{TRACK_ID}"),
+        "id": TRACK_ID,
+        "is_local": false,
+        "name": "Money",
+        "popularity": 70,
+        "preview_url": null,
+        "track_number": 6,
+        "type": "track",
+        "uri": format!("spotify:track:{TRACK_ID}")
+    })))
+}
+
+fn artist() -> Value {
+    json!({
+        "external_urls": { "spotify": "https://open.spotify.com/artist/0k17h0D3J5VfsdmQ1iZtE9" },
+        "href": "https://api.spotify.com/v1/artists/0k17h0D3J5VfsdmQ1iZtE9",
+        "id": "0k17h0D3J5VfsdmQ1iZtE9",
+        "name": "Pink Floyd",
+        "type": "artist",
+        "uri": "spotify:artist:0k17h0D3J5VfsdmQ1iZtE9"
+    })
+}
+
+async fn play(State(calls): Calls, Json(body): Json<Value>) -> StatusCode {
+    let call = match body.get("uris").and_then(Value::as_array) {
+        Some(uris) => Call::Play(
+            uris.iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+        ),
+        None => Call::Resume,
+    };
+    calls.lock().unwrap().push(call);
+    StatusCode::NO_CONTENT
+}
+
+async fn pause(State(calls): Calls) -> StatusCode {
+    calls.lock().unwrap().push(Call::Pause);
+    StatusCode::NO_CONTENT
+}
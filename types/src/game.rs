@@ -1,4 +1,17 @@
-use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+use serde::{
+    de::{MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+
+use crate::track::Track;
+
+/// The stable ID of a team.
+///
+/// Unlike its position in the list of teams, the ID of a
+/// team never changes, even if other teams are deleted.
+pub type TeamId = u64;
 
 /// The Phase a game is currently in.
 ///
@@ -10,42 +23,132 @@ pub enum Phase {
     #[default]
     Selection,
     /// The teams are listening to the currently playing song
-    /// but no-one has guessed yet.
-    Listening { active: Vec<bool> },
+    /// but no-one has guessed all of it yet.
+    Listening {
+        /// The teams which may still buzz.
+        #[serde(deserialize_with = "active_teams")]
+        active: Vec<TeamId>,
+        #[serde(default)]
+        solved: Solved,
+    },
     /// One team has guessed a song and the admin is currently
     /// deliberating whether it is correct or incorrect
-    Guessing { active: Vec<bool>, team: usize },
+    Guessing {
+        /// The teams which may still buzz.
+        #[serde(deserialize_with = "active_teams")]
+        active: Vec<TeamId>,
+        team: TeamId,
+        #[serde(default)]
+        solved: Solved,
+        /// The teams which buzzed after the guessing team, in
+        /// order, if buzzes are queued.
+        #[serde(default)]
+        queue: Vec<TeamId>,
+    },
+    /// The round at the given index is over and its
+    /// results are shown.
+    RoundOver { round: usize },
+    /// The match is over and the final standings are shown.
+    Finished,
+}
+
+/// Deserialize the teams which may still buzz, also from snapshots
+/// which stored whether each team is active by its position.
+fn active_teams<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<TeamId>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Entry {
+        Id(TeamId),
+        Flag(bool),
+    }
+    let entries = Vec::<Entry>::deserialize(deserializer)?;
+    Ok(entries
+        .into_iter()
+        .enumerate()
+        .filter_map(|(n, entry)| match entry {
+            Entry::Id(id) => Some(id),
+            Entry::Flag(true) => Some(n as TeamId),
+            Entry::Flag(false) => None,
+        })
+        .collect())
+}
+
+/// Deserialize the points of a round, also from snapshots which
+/// stored them as a list by the position of each team.
+fn round_points<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<TeamId, i64>, D::Error> {
+    struct Points;
+
+    impl<'de> Visitor<'de> for Points {
+        type Value = BTreeMap<TeamId, i64>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("points by team ID or by position")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut points = BTreeMap::new();
+            while let Some(value) = seq.next_element()? {
+                points.insert(points.len() as TeamId, value);
+            }
+            Ok(points)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut points = BTreeMap::new();
+            while let Some((id, value)) = map.next_entry()? {
+                points.insert(id, value);
+            }
+            Ok(points)
+        }
+    }
+
+    deserializer.deserialize_any(Points)
+}
+
+/// Which parts of the current song have been guessed correctly.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct Solved {
+    pub title: bool,
+    pub artist: bool,
+}
+
+impl Solved {
+    /// Check if both title and artist have been guessed.
+    pub fn complete(&self) -> bool {
+        self.title && self.artist
+    }
 }
 
 impl Phase {
-    /// Check if the team at index is active
+    /// Check if the team with the given ID is active
     ///
     /// This has different meanings depending on the game phase
     /// - In the selection phase, any team is always active
     /// - In the listening phase, only the teams who have not been disabled are active
     /// - In the guessing phase, only the team currently guessing is active
+    /// - Between rounds and after the match, no team is active
     ///
     /// # Examples
     ///
     /// ```
-    /// use types::game::Phase;
+    /// use types::game::{Phase, Solved};
     ///
+    /// let solved = Solved::default();
     /// assert_eq!(Phase::Selection.is_active(0), true);
-    /// assert_eq!(Phase::Listening{active: vec![false, false, true]}.is_active(0), false);
-    /// assert_eq!(Phase::Guessing{active: vec![], team: 6}.is_active(6), true);
-    /// assert_eq!(Phase::Guessing{active: vec![], team: 6}.is_active(2), false);
+    /// assert_eq!(Phase::Listening{active: vec![2], solved}.is_active(0), false);
+    /// let guessing = Phase::Guessing{active: vec![2, 6], team: 6, solved, queue: vec![2]};
+    /// assert_eq!(guessing.is_active(6), true);
+    /// assert_eq!(guessing.is_active(2), false);
+    /// assert_eq!(Phase::Finished.is_active(0), false);
     /// ```
-    pub fn is_active(&self, index: usize) -> bool {
+    pub fn is_active(&self, id: TeamId) -> bool {
         match self {
             Self::Selection => true,
-            Self::Listening { active } => {
-                if let Some(value) = active.get(index) {
-                    *value
-                } else {
-                    false
-                }
-            }
-            Self::Guessing { team, .. } => *team == index,
+            Self::Listening { active, .. } => active.contains(&id),
+            Self::Guessing { team, .. } => *team == id,
+            Self::RoundOver { .. } | Self::Finished => false,
         }
     }
 }
@@ -55,6 +158,93 @@ impl Phase {
 /// This stores all data relating to a team.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Team {
+    /// The stable ID of the team.
+    #[serde(default)]
+    pub id: TeamId,
+    pub name: String,
+    pub points: i64,
+    /// The players who registered a buzzer for this team.
+    #[serde(default)]
+    pub players: Vec<Player>,
+    /// The name proposed by the players of this team, while
+    /// it awaits the approval of an admin.
+    #[serde(default)]
+    pub proposed: Option<String>,
+}
+
+/// A player within a team, identified by the nickname
+/// entered when registering a buzzer.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct Player {
+    pub name: String,
+    /// How often the player won a buzz round or was
+    /// queued up to guess.
+    pub buzzes: u64,
+    /// How many guesses of the player were accepted
+    /// at least in part.
+    pub correct: u64,
+}
+
+/// A buzz of the current buzz round.
+///
+/// Near-simultaneous buzzes are ordered by the time they were
+/// pressed, compensating for the latency of each buzzer.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Buzz {
+    /// The ID of the team which buzzed.
+    pub team: TeamId,
+    /// How many milliseconds after the fastest buzz this was pressed.
+    pub delay: u64,
+    /// The nickname of the player who buzzed, if known.
+    #[serde(default)]
+    pub player: Option<String>,
+}
+
+/// A round of a match.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Round {
+    /// The name of the round, e.g. its theme.
     pub name: String,
-    pub points: usize,
+    /// How many songs are played in this round.
+    pub songs: usize,
+    /// How many points each team scored in this round,
+    /// by the ID of the team, once it is over.
+    #[serde(default, deserialize_with = "round_points")]
+    pub points: BTreeMap<TeamId, i64>,
+}
+
+/// A song which was played, as recorded in the game log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct PlayedSong {
+    /// The song which was played.
+    pub track: Track,
+    /// The index of the round the song was played in, if
+    /// the match is played in rounds.
+    pub round: Option<usize>,
+    /// When the song was started, in seconds since the Unix epoch.
+    pub started: u64,
+    /// The buzz rounds of the song, each listing the names of
+    /// the buzzing teams fastest first.
+    pub buzzes: Vec<Vec<String>>,
+    /// The guesses made for the song, in order.
+    pub guesses: Vec<Guess>,
+}
+
+/// A guess as recorded in the game log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Guess {
+    /// The name of the guessing team.
+    pub team: String,
+    /// The nickname of the guessing player, if known.
+    #[serde(default)]
+    pub player: Option<String>,
+    /// Whether the title was accepted.
+    pub title: bool,
+    /// Whether the artist was accepted.
+    pub artist: bool,
+    /// How many points the team was awarded.
+    pub points: i64,
+    /// How many milliseconds the song had been playing
+    /// when the team buzzed.
+    pub after: u64,
 }
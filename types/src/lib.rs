@@ -1,3 +1,4 @@
 pub mod game;
 pub mod message;
+pub mod scoring;
 pub mod track;
@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    game::{Phase, Team},
-    track::Track,
+    game::{Buzz, Phase, PlayedSong, Round, Team, TeamId},
+    scoring::ScoringRules,
+    track::{Audio, LibraryEntry, Track},
 };
 
 /// An update to a spectator.
@@ -15,8 +16,31 @@ pub struct SpectatorUpdate {
     pub phase: Phase,
     /// Which teams currently exist.
     pub teams: Vec<Team>,
-    /// Which song is currently revealed
+    /// Which song is currently revealed.
+    ///
+    /// While a song is only partially solved, this contains
+    /// just the parts which have been guessed.
     pub revealed: Option<Track>,
+    /// Which audio the spectator should play, if any.
+    pub audio: Option<Audio>,
+    /// The buzzes of the current buzz round, fastest first.
+    pub buzzes: Vec<Buzz>,
+    /// How many milliseconds the guessing team has left to
+    /// answer, if there is a limit.
+    pub time_left: Option<u64>,
+    /// How many milliseconds the song may be listened to in
+    /// total, if there is a limit.
+    pub listening_time: Option<u64>,
+    /// How many milliseconds the song may still be listened
+    /// to before it is revealed, while there is a limit.
+    pub listening_left: Option<u64>,
+    /// The rounds of the current match, if it is played in rounds.
+    pub rounds: Vec<Round>,
+    /// The index of the current round.
+    pub round: usize,
+    /// The most recently played songs which have been
+    /// revealed, latest first.
+    pub previous: Vec<Track>,
 }
 
 /// An update to a buzzer.
@@ -30,7 +54,19 @@ pub struct BuzzerUpdate {
     /// Which teams currently exist.
     pub teams: Vec<Team>,
     /// Which team this buzzer is registered to.
-    pub registered: Option<usize>,
+    pub registered: Option<TeamId>,
+    /// The nickname this buzzer is registered with, if any.
+    #[serde(default)]
+    pub player: Option<String>,
+    /// How many milliseconds the guessing team has left to
+    /// answer, if there is a limit.
+    pub time_left: Option<u64>,
+    /// How many milliseconds the song may be listened to in
+    /// total, if there is a limit.
+    pub listening_time: Option<u64>,
+    /// How many milliseconds the song may still be listened
+    /// to before it is revealed, while there is a limit.
+    pub listening_left: Option<u64>,
 }
 
 /// An interaction from a buzzer.
@@ -40,10 +76,20 @@ pub struct BuzzerUpdate {
 /// interaction on the buzzer side.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum BuzzerInteraction {
-    /// The buzzer wants to register for the team.
-    Register { team: usize },
+    /// The buzzer wants to register for the team, optionally
+    /// as the player with the given nickname.
+    Register {
+        team: TeamId,
+        #[serde(default)]
+        player: Option<String>,
+    },
     /// The buzzer wants to buzz for its registered team.
     Buzz,
+    /// The buzzer wants to create a team with the given name
+    /// and register for it.
+    CreateTeam { name: String },
+    /// The buzzer proposes a new name for its registered team.
+    ProposeName { name: String },
 }
 
 /// An update to an admin.
@@ -56,8 +102,59 @@ pub struct AdminUpdate {
     pub phase: Phase,
     /// Which teams currently exist.
     pub teams: Vec<Team>,
+    /// Whether buzzes are queued while a team is guessing.
+    pub queue_buzzes: bool,
     /// Which song is currently or was selected.
     pub song: Option<Track>,
+    /// Which tracks can be selected directly.
+    pub library: Vec<LibraryEntry>,
+    /// Which tracks are queued up to be played next.
+    pub queue: Vec<LibraryEntry>,
+    /// How guesses are currently scored.
+    pub rules: ScoringRules,
+    /// The buzzes of the current buzz round, fastest first.
+    pub buzzes: Vec<Buzz>,
+    /// How many milliseconds teams may take to answer, if
+    /// there is a limit.
+    pub answer_time: Option<u64>,
+    /// How many milliseconds a song may be listened to before
+    /// it is revealed, if there is a limit.
+    pub listening_time: Option<u64>,
+    /// The rounds of the current match, if it is played in rounds.
+    pub rounds: Vec<Round>,
+    /// The index of the current round.
+    pub round: usize,
+    /// How many songs of the current round have been played.
+    pub played: usize,
+    /// Which name packs new team names can be generated from.
+    pub name_packs: Vec<NamePack>,
+    /// The name pack new team names are generated from,
+    /// if not the default one.
+    pub name_pack: Option<String>,
+    /// Whether names proposed by buzzers need approval.
+    pub approve_names: bool,
+    /// How many milliseconds the guessing team has left to
+    /// answer, if there is a limit.
+    pub time_left: Option<u64>,
+}
+
+/// A themed list of words team names can be generated from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NamePack {
+    /// The name of the pack, e.g. its theme.
+    pub name: String,
+    /// The language the names are in, e.g. `de`.
+    pub language: String,
+}
+
+/// The game log sent to an admin.
+///
+/// This is sent by the server whenever a song was played
+/// or guessed, separately from the [`AdminUpdate`]s.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdminLog {
+    /// Every song played so far, oldest first.
+    pub songs: Vec<PlayedSong>,
 }
 
 /// An interaction from an admin.
@@ -67,18 +164,82 @@ pub struct AdminUpdate {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum AdminInteraction {
     /// The admin has finished the selection phase and
-    /// has decided on a song with the given Spotify ID.
-    Selection { id: String },
+    /// has decided on a song with the given ID, either
+    /// a Spotify URI or the ID of a library entry.
+    ///
+    /// Optionally only a snippet of `duration` seconds
+    /// starting `start` seconds into the song is played.
+    Selection {
+        id: String,
+        #[serde(default)]
+        start: Option<u64>,
+        #[serde(default)]
+        duration: Option<u64>,
+    },
     /// The admin has stopped a listening phase
     StopListening,
-    /// The admin has accepted a given guess
+    /// The admin has accepted a given guess as fully correct
     AcceptGuess,
+    /// The admin has accepted only the title of a given guess
+    AcceptTitle,
+    /// The admin has accepted only the artist of a given guess
+    AcceptArtist,
     /// The admin has reject a given guess
     RejectGuess,
     /// The admin has created a new team.
     CreateTeam,
     /// The admin has renamed an existing team
-    RenameTeam { team: usize, name: String },
+    RenameTeam { team: TeamId, name: String },
     /// The admin has delted an existing team
-    DeleteTeam { team: usize },
+    DeleteTeam { team: TeamId },
+    /// The admin wants an existing team to get a new
+    /// random name
+    RerollTeamName { team: TeamId },
+    /// The admin has chosen the name pack new team names are
+    /// generated from, or the default one
+    SetNamePack { pack: Option<String> },
+    /// The admin has enabled or disabled approving names
+    /// proposed by buzzers
+    SetNameApproval { enabled: bool },
+    /// The admin has approved the name proposed for a team
+    ApproveName { team: TeamId },
+    /// The admin has rejected the name proposed for a team
+    RejectName { team: TeamId },
+    /// The admin wants to append all tracks of the playlist
+    /// or album with the given ID to the queue.
+    LoadQueue { id: String },
+    /// The admin has moved a queue entry to another position
+    MoveQueueEntry { from: usize, to: usize },
+    /// The admin has removed an entry from the queue
+    RemoveQueueEntry { index: usize },
+    /// The admin has started the first song of the queue
+    PlayNext,
+    /// The admin has changed how guesses are scored
+    SetScoring { rules: ScoringRules },
+    /// The admin has added points to an existing team,
+    /// or removed them if negative
+    AdjustPoints { team: TeamId, delta: i64 },
+    /// The admin has overwritten the points of an existing team
+    SetPoints { team: TeamId, points: i64 },
+    /// The admin has reverted the last change to the game
+    Undo,
+    /// The admin has enabled or disabled queueing buzzes
+    SetBuzzQueue { enabled: bool },
+    /// The admin has changed how many milliseconds teams may
+    /// take to answer, or removed the limit
+    SetAnswerTime { millis: Option<u64> },
+    /// The admin has changed how many milliseconds a song may be
+    /// listened to before it is revealed, or removed the limit
+    SetListeningTime { millis: Option<u64> },
+    /// The admin has started a new match with the given rounds,
+    /// resetting all points
+    StartMatch { rounds: Vec<Round> },
+    /// The admin has ended the current round, or started the
+    /// next one after its results were shown
+    NextRound,
+    /// The admin has finished the match
+    FinishMatch,
+    /// The admin has left the finished match to play
+    /// songs freely
+    LeaveMatch,
 }
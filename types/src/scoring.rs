@@ -0,0 +1,137 @@
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// The rules deciding how many points a guess is worth.
+///
+/// All times are measured in seconds of the song having been
+/// played when the team buzzed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScoringRules {
+    /// Points for guessing both title and artist.
+    pub correct: i64,
+    /// Points for guessing only the title.
+    pub title: i64,
+    /// Points for guessing only the artist.
+    pub artist: i64,
+    /// Points for a rejected guess, usually zero or negative.
+    pub wrong: i64,
+    /// Bonus points for a correct guess within `fast_window`.
+    pub fast_bonus: i64,
+    /// How many seconds a guess counts as fast.
+    pub fast_window: u64,
+    /// Points lost for every `decay_interval` seconds played.
+    pub decay: i64,
+    /// How many seconds it takes to lose `decay` points,
+    /// zero disables decay.
+    pub decay_interval: u64,
+    /// The least a correct guess can decay to.
+    pub minimum: i64,
+}
+
+/// The names of all presets accepted by [`ScoringRules::from_str`].
+pub const PRESETS: &[&str] = &["classic", "split", "strict", "speed"];
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        Self {
+            correct: 1,
+            title: 0,
+            artist: 0,
+            wrong: 0,
+            fast_bonus: 0,
+            fast_window: 0,
+            decay: 0,
+            decay_interval: 0,
+            minimum: 0,
+        }
+    }
+}
+
+impl ScoringRules {
+    /// The rules a preset stands for.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "classic" => Some(Self::default()),
+            "split" => Some(Self {
+                correct: 2,
+                title: 1,
+                artist: 1,
+                ..Default::default()
+            }),
+            "strict" => Some(Self {
+                wrong: -1,
+                ..Self::preset("split")?
+            }),
+            "speed" => Some(Self {
+                correct: 5,
+                title: 3,
+                artist: 2,
+                fast_bonus: 2,
+                fast_window: 5,
+                decay: 1,
+                decay_interval: 10,
+                minimum: 1,
+                ..Default::default()
+            }),
+            _ => None,
+        }
+    }
+
+    /// Points for a guess after the song has played for `played`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use types::scoring::ScoringRules;
+    ///
+    /// let rules = ScoringRules::preset("speed").unwrap();
+    /// assert_eq!(rules.award(true, true, Duration::from_secs(3)), 7);
+    /// assert_eq!(rules.award(true, true, Duration::from_secs(25)), 3);
+    /// assert_eq!(rules.award(false, true, Duration::from_secs(90)), 1);
+    /// assert_eq!(rules.award(false, false, Duration::from_secs(3)), 0);
+    /// ```
+    pub fn award(&self, title: bool, artist: bool, played: Duration) -> i64 {
+        let base = match (title, artist) {
+            (true, true) => self.correct,
+            (true, false) => self.title,
+            (false, true) => self.artist,
+            (false, false) => return 0,
+        };
+        let seconds = played.as_secs();
+        let decay = match self.decay_interval {
+            0 => 0,
+            interval => (seconds / interval) as i64 * self.decay,
+        };
+        let bonus = if seconds < self.fast_window {
+            self.fast_bonus
+        } else {
+            0
+        };
+        (base - decay).max(self.minimum.min(base)) + bonus
+    }
+}
+
+/// The error returned when parsing an unknown preset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownPreset(pub String);
+
+impl Display for UnknownPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unknown scoring preset {}, expected one of {}",
+            self.0,
+            PRESETS.join(", ")
+        )
+    }
+}
+
+impl FromStr for ScoringRules {
+    type Err = UnknownPreset;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::preset(s).ok_or_else(|| UnknownPreset(s.to_string()))
+    }
+}
@@ -27,3 +27,21 @@ impl From<FullTrack> for Track {
         }
     }
 }
+
+/// A track the admin can select by its id.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LibraryEntry {
+    pub id: String,
+    pub track: Track,
+}
+
+/// Audio which is played directly by the spectator page.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Audio {
+    /// Where the audio file is served from.
+    pub url: String,
+    /// Whether the audio should currently be playing.
+    pub playing: bool,
+    /// The position in milliseconds playback was last moved to.
+    pub position: u64,
+}